@@ -0,0 +1,59 @@
+//! RPC methods reading the claim history that `pallet-poe` writes to the offchain index.
+//!
+//! The node must run with `--enable-offchain-indexing true`, otherwise the history is empty.
+
+use std::sync::Arc;
+
+use codec::{Codec, Decode};
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use pallet_poe::{history_key, ClaimHistoryRecord};
+use parking_lot::RwLock;
+use serde::{de::DeserializeOwned, Serialize};
+use sp_core::offchain::OffchainStorage;
+
+/// Most records returned for one account.
+pub const MAX_HISTORY_LEN: u32 = 1024;
+
+#[rpc]
+pub trait PoeApi<AccountId, BlockNumber> {
+	/// The claims `account` created, revoked, sent or received, oldest first.
+	#[rpc(name = "poe_history")]
+	fn history(&self, account: AccountId) -> Result<Vec<ClaimHistoryRecord<AccountId, BlockNumber>>>;
+}
+
+/// Serves `PoeApi` from the offchain database of the node.
+pub struct Poe<S> {
+	storage: Arc<RwLock<S>>,
+}
+
+impl<S> Poe<S> {
+	pub fn new(storage: S) -> Self {
+		Poe { storage: Arc::new(RwLock::new(storage)) }
+	}
+}
+
+impl<S, AccountId, BlockNumber> PoeApi<AccountId, BlockNumber> for Poe<S>
+where
+	S: OffchainStorage + 'static,
+	AccountId: Codec + Serialize + DeserializeOwned + Send + Sync + 'static,
+	BlockNumber: Codec + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+	fn history(&self, account: AccountId) -> Result<Vec<ClaimHistoryRecord<AccountId, BlockNumber>>> {
+		let storage = self.storage.read();
+		let mut records = Vec::new();
+		for n in 0..MAX_HISTORY_LEN {
+			let raw = match storage.get(sp_offchain::STORAGE_PREFIX, &history_key(&account, n)) {
+				Some(raw) => raw,
+				None => break,
+			};
+			let record = Decode::decode(&mut &raw[..]).map_err(|e| RpcError {
+				code: ErrorCode::InternalError,
+				message: "Unable to decode claim history record.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})?;
+			records.push(record);
+		}
+		Ok(records)
+	}
+}
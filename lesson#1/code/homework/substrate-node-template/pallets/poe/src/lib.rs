@@ -1,10 +1,14 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 // 1. Imports
+use codec::{Decode, Encode};
 use frame_support::{
     dispatch, decl_module, decl_storage, decl_event, decl_error, ensure, StorageMap,
 };
 use frame_system::ensure_signed;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::RuntimeDebug;
 use sp_std::vec::Vec;
 
 
@@ -23,6 +27,38 @@ mod tests_revoke;
 #[cfg(test)]
 mod tests_transfer;
 
+#[cfg(test)]
+mod tests_proptest;
+
+/// Prefix of the offchain index keys of claim history records.
+pub const HISTORY_INDEX_PREFIX: &[u8] = b"poe::history";
+
+/// What an account did with a claim.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum ClaimAction<AccountId> {
+    Created,
+    Revoked,
+    /// The claim was handed over to `to`.
+    TransferredOut { to: AccountId },
+    /// The claim was received from `from`.
+    TransferredIn { from: AccountId },
+}
+
+/// A history record written to the offchain index, so clients don't need to scan events.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct ClaimHistoryRecord<AccountId, BlockNumber> {
+    pub block: BlockNumber,
+    pub proof: Vec<u8>,
+    pub action: ClaimAction<AccountId>,
+}
+
+/// Offchain index key of the `n`th history record of `account`.
+pub fn history_key<AccountId: Encode>(account: &AccountId, n: u32) -> Vec<u8> {
+    (HISTORY_INDEX_PREFIX, account, n).encode()
+}
+
 // 2. Configuration
 /// Configure the pallet by specifying the parameters and types on which it depends.
 pub trait Trait: frame_system::Trait {
@@ -38,6 +74,8 @@ decl_storage! {
         /// The storage item for our proofs.
         /// It maps a proof to the user who made the claim and when they made it.
         Proofs: map hasher(blake2_128_concat) Vec<u8> => (T::AccountId, T::BlockNumber);
+        /// Number of claim history records written to the offchain index for an account.
+        pub ClaimHistoryLen get(fn claim_history_len): map hasher(blake2_128_concat) T::AccountId => u32;
     }
 }
 
@@ -104,6 +142,7 @@ decl_module! {
 
             // Store the proof with the sender and block number.
             Proofs::<T>::insert(&proof, (&sender, current_block));
+            Self::index_history(&sender, &proof, ClaimAction::Created);
 
             // Emit an event that the claim was created.
             Self::deposit_event(RawEvent::ClaimCreated(sender, proof));
@@ -130,6 +169,7 @@ decl_module! {
 
             // Remove claim from storage.
             Proofs::<T>::remove(&proof);
+            Self::index_history(&sender, &proof, ClaimAction::Revoked);
 
             // Emit an event that the claim was erased.
             Self::deposit_event(RawEvent::ClaimRevoked(sender, proof));
@@ -148,9 +188,26 @@ decl_module! {
             ensure!(sender == owner, Error::<T>::NotProofOwner);
 
             let current_block = <frame_system::Module<T>>::block_number();
-            Proofs::<T>::insert(&proof, (dest, current_block));
+            Proofs::<T>::insert(&proof, (&dest, current_block));
+            Self::index_history(&sender, &proof, ClaimAction::TransferredOut { to: dest.clone() });
+            Self::index_history(&dest, &proof, ClaimAction::TransferredIn { from: sender });
 
             Ok(())
         }
     }
-}
\ No newline at end of file
+}
+
+impl<T: Trait> Module<T> {
+    /// Write a history record of `who` to the offchain index. The node only keeps it when
+    /// started with `--enable-offchain-indexing true`.
+    fn index_history(who: &T::AccountId, proof: &[u8], action: ClaimAction<T::AccountId>) {
+        let n = Self::claim_history_len(who);
+        let record = ClaimHistoryRecord {
+            block: <frame_system::Module<T>>::block_number(),
+            proof: proof.to_vec(),
+            action,
+        };
+        sp_io::offchain_index::set(&history_key(who, n), &record.encode());
+        ClaimHistoryLen::<T>::insert(who, n + 1);
+    }
+}
@@ -54,6 +54,7 @@ impl Trait for Test {
 }
 
 pub type PoeModule = Module<Test>;
+pub type System = system::Module<Test>;
 
 // Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
@@ -1,5 +1,6 @@
 use crate::{Error, mock::*};
 use frame_support::{assert_ok, assert_noop};
+use sp_core::offchain::OffchainStorage;
 use super::*;
 
 
@@ -48,4 +49,40 @@ fn revoke_claim_failed_when_claim_is_not_exist() {
             Error::<Test>::NoSuchProof
         );
     })
-}
\ No newline at end of file
+}
+
+#[test]
+fn claim_history_is_indexed() {
+    let mut ext = new_test_ext();
+    ext.execute_with(|| {
+        let claim = vec![0, 1];
+        assert_ok!(PoeModule::create_claim(Origin::signed(1), claim.clone()));
+        assert_eq!(PoeModule::claim_history_len(1), 1);
+
+        assert_ok!(PoeModule::transfer_claim(Origin::signed(1), claim.clone(), 2));
+        assert_eq!(PoeModule::claim_history_len(1), 2);
+        assert_eq!(PoeModule::claim_history_len(2), 1);
+
+        assert_ok!(PoeModule::revoke_claim(Origin::signed(2), claim.clone()));
+        assert_eq!(PoeModule::claim_history_len(2), 2);
+
+        assert_noop!(
+            PoeModule::revoke_claim(Origin::signed(2), claim.clone()),
+            Error::<Test>::NoSuchProof
+        );
+        assert_eq!(PoeModule::claim_history_len(2), 2);
+    });
+
+    ext.persist_offchain_overlay();
+    let raw = ext.offchain_db()
+        .get(sp_core::offchain::STORAGE_PREFIX, &history_key(&2u64, 0))
+        .expect("the first record of account 2 is indexed");
+    assert_eq!(
+        ClaimHistoryRecord::<u64, u64>::decode(&mut &raw[..]).unwrap(),
+        ClaimHistoryRecord {
+            block: 0,
+            proof: vec![0, 1],
+            action: ClaimAction::TransferredIn { from: 1 },
+        }
+    );
+}
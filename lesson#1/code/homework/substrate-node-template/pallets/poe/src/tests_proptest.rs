@@ -0,0 +1,121 @@
+//! Random sequences of `create_claim`, `revoke_claim` and `transfer_claim`, checked against a
+//! model of the claims after every step.
+//!
+//! Failing sequences are shrunk and their seeds saved to `proptest-regressions/tests_proptest.txt`,
+//! which later runs replay first. Set `PROPTEST_CASES` to run more cases.
+
+use std::collections::BTreeMap;
+
+use crate::mock::*;
+use frame_support::{storage_root, IterableStorageMap};
+use proptest::prelude::*;
+use super::*;
+
+const ACCOUNTS: u64 = 3;
+
+#[derive(Clone, Debug)]
+enum Op {
+    Create { who: u64, proof: Vec<u8> },
+    Revoke { who: u64, proof: Vec<u8> },
+    Transfer { who: u64, proof: Vec<u8>, dest: u64 },
+    NextBlock,
+}
+
+fn account() -> impl Strategy<Value = u64> {
+    1..=ACCOUNTS
+}
+
+/// Mostly a few short proofs so calls hit the same claims, sometimes an invalid size.
+fn proof() -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![
+        8 => proptest::collection::vec(0u8..3, 1..3),
+        1 => Just(vec![]),
+        1 => Just(vec![0; 10241]),
+    ]
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        3 => (account(), proof()).prop_map(|(who, proof)| Op::Create { who, proof }),
+        2 => (account(), proof()).prop_map(|(who, proof)| Op::Revoke { who, proof }),
+        2 => (account(), proof(), account()).prop_map(|(who, proof, dest)| Op::Transfer { who, proof, dest }),
+        1 => Just(Op::NextBlock),
+    ]
+}
+
+fn run(ops: Vec<Op>) {
+    new_test_ext().execute_with(|| {
+        // proof => (owner, block it was claimed or transferred at)
+        let mut claims: BTreeMap<Vec<u8>, (u64, u64)> = BTreeMap::new();
+        let mut history_len: BTreeMap<u64, u32> = BTreeMap::new();
+
+        for op in ops {
+            let root = storage_root();
+            let block = System::block_number();
+            let result = match op.clone() {
+                Op::Create { who, proof } => {
+                    PoeModule::create_claim(Origin::signed(who), proof.clone()).map(|_| {
+                        claims.insert(proof, (who, block));
+                        *history_len.entry(who).or_default() += 1;
+                    })
+                },
+                Op::Revoke { who, proof } => {
+                    PoeModule::revoke_claim(Origin::signed(who), proof.clone()).map(|_| {
+                        claims.remove(&proof);
+                        *history_len.entry(who).or_default() += 1;
+                    })
+                },
+                Op::Transfer { who, proof, dest } => {
+                    PoeModule::transfer_claim(Origin::signed(who), proof.clone(), dest).map(|_| {
+                        claims.insert(proof, (dest, block));
+                        *history_len.entry(who).or_default() += 1;
+                        *history_len.entry(dest).or_default() += 1;
+                    })
+                },
+                Op::NextBlock => {
+                    System::set_block_number(block + 1);
+                    Ok(())
+                },
+            };
+            // A failed call must not touch the storage.
+            if result.is_err() {
+                assert_eq!(storage_root(), root, "{:?} failed but changed the storage", op);
+            }
+
+            let stored: BTreeMap<_, _> = Proofs::<Test>::iter().collect();
+            assert_eq!(stored, claims, "claims after {:?}", op);
+            for who in 1..=ACCOUNTS {
+                assert_eq!(
+                    PoeModule::claim_history_len(who),
+                    history_len.get(&who).copied().unwrap_or_default(),
+                    "history length of {} after {:?}", who, op,
+                );
+            }
+        }
+    })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn random_calls_match_the_model(ops in proptest::collection::vec(op(), 1..60)) {
+        run(ops);
+    }
+}
+
+/// A fixed sequence that reaches every call and error.
+#[test]
+fn fixed_sequence_matches_the_model() {
+    run(vec![
+        Op::Create { who: 1, proof: vec![0] },
+        Op::Create { who: 2, proof: vec![0] },
+        Op::Create { who: 2, proof: vec![] },
+        Op::NextBlock,
+        Op::Transfer { who: 1, proof: vec![0], dest: 2 },
+        Op::Revoke { who: 1, proof: vec![0] },
+        Op::Transfer { who: 2, proof: vec![0], dest: 2 },
+        Op::Revoke { who: 2, proof: vec![0] },
+        Op::Revoke { who: 2, proof: vec![0] },
+    ]);
+}
@@ -1,12 +1,15 @@
-use sp_core::{Pair, Public, sr25519};
+use sp_core::{Pair, Public, sr25519, crypto::Ss58Codec};
 use node_template_runtime::{
-	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig,
-	SudoConfig, SystemConfig, WASM_BINARY, Signature
+	AccountId, AuraConfig, Balance, BalancesConfig, CouncilConfig, CouncilMembershipConfig,
+	DefaultParameters, GenesisConfig, GrandpaConfig, KittiesModuleConfig, KittyIndex,
+	SystemConfig, TreasuryConfig, WASM_BINARY, Signature
 };
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_finality_grandpa::AuthorityId as GrandpaId;
 use sp_runtime::traits::{Verify, IdentifyAccount};
 use sc_service::ChainType;
+use serde::{Deserialize, Deserializer};
+use crate::kitties_snapshot;
 
 // The URL for the telemetry server.
 // const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
@@ -53,8 +56,6 @@ pub fn development_config() -> Result<ChainSpec, String> {
 			vec![
 				authority_keys_from_seed("Alice"),
 			],
-			// Sudo account
-			get_account_id_from_seed::<sr25519::Public>("Alice"),
 			// Pre-funded accounts
 			vec![
 				get_account_id_from_seed::<sr25519::Public>("Alice"),
@@ -62,6 +63,11 @@ pub fn development_config() -> Result<ChainSpec, String> {
 				get_account_id_from_seed::<sr25519::Public>("Alice//stash"),
 				get_account_id_from_seed::<sr25519::Public>("Bob//stash"),
 			],
+			// Council members
+			vec![
+				get_account_id_from_seed::<sr25519::Public>("Alice"),
+				get_account_id_from_seed::<sr25519::Public>("Bob"),
+			],
 			true,
 		),
 		// Bootnodes
@@ -93,8 +99,6 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 				authority_keys_from_seed("Alice"),
 				authority_keys_from_seed("Bob"),
 			],
-			// Sudo account
-			get_account_id_from_seed::<sr25519::Public>("Alice"),
 			// Pre-funded accounts
 			vec![
 				get_account_id_from_seed::<sr25519::Public>("Alice"),
@@ -110,6 +114,12 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 				get_account_id_from_seed::<sr25519::Public>("Eve//stash"),
 				get_account_id_from_seed::<sr25519::Public>("Ferdie//stash"),
 			],
+			// Council members
+			vec![
+				get_account_id_from_seed::<sr25519::Public>("Alice"),
+				get_account_id_from_seed::<sr25519::Public>("Bob"),
+				get_account_id_from_seed::<sr25519::Public>("Charlie"),
+			],
 			true,
 		),
 		// Bootnodes
@@ -129,9 +139,29 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 fn testnet_genesis(
 	wasm_binary: &[u8],
 	initial_authorities: Vec<(AuraId, GrandpaId)>,
-	root_key: AccountId,
 	endowed_accounts: Vec<AccountId>,
+	council: Vec<AccountId>,
 	_enable_println: bool,
+) -> GenesisConfig {
+	genesis(
+		wasm_binary,
+		initial_authorities,
+		// Configure endowed accounts with initial balance of 1 << 60.
+		endowed_accounts.into_iter().map(|k| (k, 1 << 60)).collect(),
+		council,
+		vec![],
+		vec![],
+	)
+}
+
+/// Genesis state with explicit balances, council and kitties.
+fn genesis(
+	wasm_binary: &[u8],
+	initial_authorities: Vec<(AuraId, GrandpaId)>,
+	balances: Vec<(AccountId, Balance)>,
+	council: Vec<AccountId>,
+	kitties: Vec<(AccountId, [u8; 16])>,
+	relations: Vec<(KittyIndex, KittyIndex, KittyIndex)>,
 ) -> GenesisConfig {
 	GenesisConfig {
 		frame_system: Some(SystemConfig {
@@ -140,8 +170,7 @@ fn testnet_genesis(
 			changes_trie_config: Default::default(),
 		}),
 		pallet_balances: Some(BalancesConfig {
-			// Configure endowed accounts with initial balance of 1 << 60.
-			balances: endowed_accounts.iter().cloned().map(|k|(k, 1 << 60)).collect(),
+			balances,
 		}),
 		pallet_aura: Some(AuraConfig {
 			authorities: initial_authorities.iter().map(|x| (x.0.clone())).collect(),
@@ -149,9 +178,195 @@ fn testnet_genesis(
 		pallet_grandpa: Some(GrandpaConfig {
 			authorities: initial_authorities.iter().map(|x| (x.1.clone(), 1)).collect(),
 		}),
-		pallet_sudo: Some(SudoConfig {
-			// Assign network admin rights.
-			key: root_key,
+		pallet_treasury: Some(TreasuryConfig::default()),
+		// Members are set by the membership pallet.
+		pallet_collective_Instance1: Some(CouncilConfig::default()),
+		pallet_membership_Instance1: Some(CouncilMembershipConfig {
+			members: council,
+			phantom: Default::default(),
+		}),
+		pallet_kitties: Some(KittiesModuleConfig {
+			kitties,
+			relations,
 		}),
 	}
 }
+
+/// A network described in a TOML or JSON file, see `generate-spec --help`.
+///
+/// Keys and accounts are SS58 addresses, or dev seeds such as `//Alice`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpecDescription {
+	pub name: String,
+	pub id: String,
+	#[serde(default = "default_chain_type")]
+	pub chain_type: ChainType,
+	/// Initial PoA authorities.
+	pub authorities: Vec<AuthorityDescription>,
+	/// Initial council members, who change runtime parameters, upgrade the runtime and approve
+	/// treasury spends.
+	/// At least one is required.
+	#[serde(default)]
+	pub council: Vec<String>,
+	#[serde(default)]
+	pub endowed: Vec<EndowedDescription>,
+	#[serde(default)]
+	pub kitties: Vec<KittyDescription>,
+	/// A JSON or CSV file written by `export-kitties`, relative to the description. Its kitties
+	/// keep their ids and relations and come before `kitties`.
+	pub kitties_snapshot: Option<std::path::PathBuf>,
+	#[serde(default)]
+	pub boot_nodes: Vec<String>,
+	pub protocol_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuthorityDescription {
+	pub aura: String,
+	pub grandpa: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EndowedDescription {
+	pub account: String,
+	/// A number, or a decimal string for amounts that don't fit in TOML/JSON integers.
+	#[serde(deserialize_with = "deserialize_balance")]
+	pub balance: Balance,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KittyDescription {
+	pub owner: String,
+	/// 16 bytes, hex encoded.
+	pub dna: String,
+}
+
+fn default_chain_type() -> ChainType {
+	ChainType::Live
+}
+
+fn deserialize_balance<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Balance, D::Error> {
+	#[derive(Deserialize)]
+	#[serde(untagged)]
+	enum Amount {
+		Number(u64),
+		Text(String),
+	}
+
+	match Amount::deserialize(deserializer)? {
+		Amount::Number(n) => Ok(n.into()),
+		Amount::Text(s) => s.parse().map_err(serde::de::Error::custom),
+	}
+}
+
+/// Parse a public key given as an SS58 address or a dev seed such as `//Alice`.
+fn parse_public<TPublic: Public + Ss58Codec>(s: &str) -> Result<TPublic, String> {
+	if s.starts_with("//") {
+		TPublic::Pair::from_string(s, None)
+			.map(|pair| pair.public())
+			.map_err(|e| format!("Invalid seed {}: {:?}", s, e))
+	} else {
+		TPublic::from_ss58check(s).map_err(|e| format!("Invalid address {}: {:?}", s, e))
+	}
+}
+
+pub fn parse_account(s: &str) -> Result<AccountId, String> {
+	if s.starts_with("//") {
+		parse_public::<sr25519::Public>(s).map(|public| AccountPublic::from(public).into_account())
+	} else {
+		AccountId::from_ss58check(s).map_err(|e| format!("Invalid account {}: {:?}", s, e))
+	}
+}
+
+pub fn parse_dna(s: &str) -> Result<[u8; 16], String> {
+	let bytes = hex::decode(s.trim_start_matches("0x"))
+		.map_err(|e| format!("Invalid kitty dna {}: {}", s, e))?;
+	let mut dna = [0u8; 16];
+	if bytes.len() != dna.len() {
+		return Err(format!("Kitty dna {} is not 16 bytes", s));
+	}
+	dna.copy_from_slice(&bytes);
+	Ok(dna)
+}
+
+impl SpecDescription {
+	/// Parse a description, as TOML if `path` ends with `.toml` and as JSON otherwise.
+	pub fn from_file(path: &std::path::Path) -> Result<Self, String> {
+		let content = std::fs::read_to_string(path)
+			.map_err(|e| format!("Error reading {}: {}", path.display(), e))?;
+		let mut description: Self = if path.extension().map_or(false, |ext| ext == "toml") {
+			toml::from_str(&content).map_err(|e| format!("Error parsing {}: {}", path.display(), e))?
+		} else {
+			serde_json::from_str(&content).map_err(|e| format!("Error parsing {}: {}", path.display(), e))?
+		};
+		if let (Some(snapshot), Some(dir)) = (&description.kitties_snapshot, path.parent()) {
+			description.kitties_snapshot = Some(dir.join(snapshot));
+		}
+		Ok(description)
+	}
+
+	/// Build the chain spec. All keys are checked here, so a typo fails before any output.
+	pub fn into_chain_spec(self) -> Result<ChainSpec, String> {
+		let wasm_binary = WASM_BINARY.ok_or("Wasm binary not available".to_string())?;
+
+		let authorities = self.authorities.iter()
+			.map(|a| Ok((parse_public::<AuraId>(&a.aura)?, parse_public::<GrandpaId>(&a.grandpa)?)))
+			.collect::<Result<Vec<_>, String>>()?;
+		if authorities.is_empty() {
+			return Err("At least one authority is required".into());
+		}
+		let balances = self.endowed.iter()
+			.map(|e| Ok((parse_account(&e.account)?, e.balance)))
+			.collect::<Result<Vec<_>, String>>()?;
+		let council = self.council.iter()
+			.map(|c| parse_account(c))
+			.collect::<Result<Vec<_>, String>>()?;
+		if council.is_empty() {
+			return Err("At least one council member is required".into());
+		}
+		let (mut kitties, relations) = match &self.kitties_snapshot {
+			Some(path) => kitties_snapshot::into_genesis(kitties_snapshot::load(path)?, parse_account, parse_dna)?,
+			None => (vec![], vec![]),
+		};
+		for k in &self.kitties {
+			kitties.push((parse_account(&k.owner)?, parse_dna(&k.dna)?));
+		}
+		// Genesis fails if an owner can't afford the kitty lock, report it here instead.
+		let lock = DefaultParameters::get().kitty_lock_token;
+		for (owner, _) in &kitties {
+			let balance = balances.iter().find(|(account, _)| account == owner).map_or(0, |(_, b)| *b);
+			if balance < lock {
+				return Err(format!(
+					"Kitty owner {} needs an endowed balance of at least {}",
+					owner.to_ss58check(), lock,
+				));
+			}
+		}
+		let boot_nodes = self.boot_nodes.iter()
+			.map(|node| node.parse().map_err(|e| format!("Invalid boot node {}: {}", node, e)))
+			.collect::<Result<Vec<_>, String>>()?;
+
+		Ok(ChainSpec::from_genesis(
+			&self.name,
+			&self.id,
+			self.chain_type,
+			move || genesis(
+				wasm_binary,
+				authorities.clone(),
+				balances.clone(),
+				council.clone(),
+				kitties.clone(),
+				relations.clone(),
+			),
+			boot_nodes,
+			None,
+			self.protocol_id.as_deref(),
+			None,
+			None,
+		))
+	}
+}
@@ -1,5 +1,7 @@
+use std::path::PathBuf;
 use structopt::StructOpt;
-use sc_cli::RunCmd;
+use sc_cli::{BlockNumberOrHash, DatabaseParams, PruningParams, RunCmd, SharedParams};
+use crate::kitties_snapshot::SnapshotFormat;
 
 #[derive(Debug, StructOpt)]
 pub struct Cli {
@@ -15,6 +17,9 @@ pub enum Subcommand {
 	/// Build a chain specification.
 	BuildSpec(sc_cli::BuildSpecCmd),
 
+	/// Generate a raw chain specification from a TOML or JSON network description.
+	GenerateSpec(GenerateSpecCmd),
+
 	/// Validate blocks.
 	CheckBlock(sc_cli::CheckBlockCmd),
 
@@ -24,6 +29,9 @@ pub enum Subcommand {
 	/// Export the state of a given block into a chain spec.
 	ExportState(sc_cli::ExportStateCmd),
 
+	/// Export all kitties at a given block as JSON or CSV.
+	ExportKitties(ExportKittiesCmd),
+
 	/// Import blocks.
 	ImportBlocks(sc_cli::ImportBlocksCmd),
 
@@ -33,7 +41,94 @@ pub enum Subcommand {
 	/// Revert the chain to a previous state.
 	Revert(sc_cli::RevertCmd),
 
+	/// Run a candidate runtime's upgrade hooks on a copy of the state and check the invariants.
+	TryUpgrade(TryUpgradeCmd),
+
 	/// The custom benchmark subcommmand benchmarking runtime pallets.
 	#[structopt(name = "benchmark", about = "Benchmark runtime pallets.")]
 	Benchmark(frame_benchmarking_cli::BenchmarkCmd),
 }
+
+/// The `generate-spec` command.
+///
+/// The description has `name`, `id`, optional `chain_type` (default `Live`), `council`,
+/// `authorities` (each with `aura` and `grandpa` keys), `endowed` (each with `account` and
+/// `balance`), `kitties` (each with `owner` and a 16 byte hex `dna`), and optional
+/// `boot_nodes` and `protocol_id`. Keys and accounts are SS58 addresses or dev seeds
+/// such as `//Alice`.
+#[derive(Debug, StructOpt)]
+pub struct GenerateSpecCmd {
+	/// The network description, parsed as TOML if it ends with `.toml` and as JSON otherwise.
+	#[structopt(parse(from_os_str))]
+	pub description: PathBuf,
+
+	/// Write the chain spec to this file instead of stdout.
+	#[structopt(long, short, parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	/// Output the human readable genesis instead of the raw storage.
+	#[structopt(long)]
+	pub human_readable: bool,
+}
+
+/// The `export-kitties` command.
+///
+/// The snapshot can be loaded into a new chain with the `kitties_snapshot` entry of a
+/// `generate-spec` description.
+#[derive(Debug, StructOpt)]
+pub struct ExportKittiesCmd {
+	/// Block hash or number, defaults to the best block.
+	#[structopt(long, value_name = "HASH or NUMBER")]
+	pub at: Option<BlockNumberOrHash>,
+
+	/// `json` or `csv`, defaults to the extension of `--output`, or json.
+	#[structopt(long)]
+	pub format: Option<SnapshotFormat>,
+
+	/// Write the snapshot to this file instead of stdout.
+	#[structopt(long, short, parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+/// The `try-upgrade` command.
+///
+/// Nothing is written back, the upgrade runs on an in-memory copy of the state.
+#[derive(Debug, StructOpt)]
+pub struct TryUpgradeCmd {
+	/// The candidate runtime Wasm blob.
+	#[structopt(long, parse(from_os_str))]
+	pub wasm: PathBuf,
+
+	/// Load the state from a chain spec with raw storage, such as one written by
+	/// `export-state`, instead of the database.
+	#[structopt(long, parse(from_os_str), conflicts_with = "at")]
+	pub snapshot: Option<PathBuf>,
+
+	/// Block hash or number of the database state, defaults to the best block.
+	#[structopt(long, value_name = "HASH or NUMBER")]
+	pub at: Option<BlockNumberOrHash>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+}
@@ -16,10 +16,19 @@
 // limitations under the License.
 
 use crate::{chain_spec, service};
-use crate::cli::{Cli, Subcommand};
+use crate::cli::{Cli, ExportKittiesCmd, GenerateSpecCmd, Subcommand, TryUpgradeCmd};
+use crate::kitties_snapshot::{self, SnapshotFormat};
+use sc_cli::{CliConfiguration, DatabaseParams, PruningParams, SharedParams};
 use sc_cli::{SubstrateCli, RuntimeVersion, Role, ChainSpec};
+use sc_client_api::{Backend, StorageProvider};
+use sp_blockchain::HeaderBackend;
+use sp_core::storage::{well_known_keys, Storage, StorageKey};
+use sp_runtime::{generic::BlockId, BuildStorage};
+use codec::Decode;
+use node_template_runtime::upgrade_check::UpgradeReport;
+use sc_executor::WasmExecutionMethod;
 use sc_service::PartialComponents;
-use node_template_runtime::Block;
+use node_template_runtime::{Block, opaque::Block as OpaqueBlock};
 
 impl SubstrateCli for Cli {
 	fn impl_name() -> String {
@@ -70,6 +79,7 @@ pub fn run() -> sc_cli::Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			runner.sync_run(|config| cmd.run(config.chain_spec, config.network))
 		},
+		Some(Subcommand::GenerateSpec(cmd)) => cmd.run(),
 		Some(Subcommand::CheckBlock(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
@@ -94,6 +104,13 @@ pub fn run() -> sc_cli::Result<()> {
 				Ok((cmd.run(client, config.chain_spec), task_manager))
 			})
 		},
+		Some(Subcommand::ExportKitties(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				let PartialComponents { client, .. } = service::new_partial(&config)?;
+				cmd.run(&*client)
+			})
+		},
 		Some(Subcommand::ImportBlocks(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
@@ -114,6 +131,19 @@ pub fn run() -> sc_cli::Result<()> {
 				Ok((cmd.run(client, backend), task_manager))
 			})
 		},
+		Some(Subcommand::TryUpgrade(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				let storage = match &cmd.snapshot {
+					Some(path) => chain_spec::ChainSpec::from_json_file(path.clone())?.build_storage()?,
+					None => {
+						let PartialComponents { client, .. } = service::new_partial(&config)?;
+						cmd.read_state(&*client)?
+					},
+				};
+				cmd.run(storage, config.wasm_method, config.default_heap_pages)
+			})
+		},
 		Some(Subcommand::Benchmark(cmd)) => {
 			if cfg!(feature = "runtime-benchmarks") {
 				let runner = cli.create_runner(cmd)?;
@@ -133,3 +163,143 @@ pub fn run() -> sc_cli::Result<()> {
 		}
 	}
 }
+
+impl GenerateSpecCmd {
+	/// Run the command. It needs no node configuration, so it doesn't go through a runner.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let spec = chain_spec::SpecDescription::from_file(&self.description)?.into_chain_spec()?;
+		let json = spec.as_json(!self.human_readable)?;
+		match &self.output {
+			Some(path) => std::fs::write(path, json)?,
+			None => println!("{}", json),
+		}
+		Ok(())
+	}
+}
+
+impl ExportKittiesCmd {
+	/// Run the command on the client of the configured chain.
+	pub fn run<C, BE>(&self, client: &C) -> sc_cli::Result<()>
+	where
+		C: StorageProvider<OpaqueBlock, BE> + HeaderBackend<OpaqueBlock>,
+		BE: Backend<OpaqueBlock>,
+	{
+		let at = match &self.at {
+			Some(at) => at.parse::<OpaqueBlock>()?,
+			None => BlockId::Hash(client.info().best_hash),
+		};
+		let hash = client.expect_block_hash_from_id(&at)?;
+		let snapshot = kitties_snapshot::export(client, BlockId::Hash(hash), format!("{:?}", hash))?;
+
+		let format = self.format.unwrap_or_else(|| {
+			self.output.as_deref().map_or(SnapshotFormat::Json, SnapshotFormat::of_path)
+		});
+		let content = snapshot.to_string(format)?;
+		match &self.output {
+			Some(path) => std::fs::write(path, content)?,
+			None => println!("{}", content),
+		}
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ExportKittiesCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+/// Heap pages used when the chain spec doesn't set any.
+const DEFAULT_HEAP_PAGES: u64 = 2048;
+
+impl TryUpgradeCmd {
+	/// Read the whole state at `--at` from the database.
+	pub fn read_state<C, BE>(&self, client: &C) -> sc_cli::Result<Storage>
+	where
+		C: StorageProvider<OpaqueBlock, BE> + HeaderBackend<OpaqueBlock>,
+		BE: Backend<OpaqueBlock>,
+	{
+		let at = match &self.at {
+			Some(at) => at.parse::<OpaqueBlock>()?,
+			None => BlockId::Hash(client.info().best_hash),
+		};
+		let top = client.storage_pairs(&at, &StorageKey(vec![]))?
+			.into_iter()
+			.map(|(key, value)| (key.0, value.0))
+			.collect();
+		// This runtime has no child tries.
+		Ok(Storage { top, children_default: Default::default() })
+	}
+
+	/// Upgrade `storage` to the candidate runtime and report the broken invariants.
+	pub fn run(
+		&self,
+		mut storage: Storage,
+		method: WasmExecutionMethod,
+		heap_pages: Option<u64>,
+	) -> sc_cli::Result<()> {
+		let heap_pages = heap_pages.unwrap_or(DEFAULT_HEAP_PAGES);
+		let candidate = std::fs::read(&self.wasm)?;
+		let current = storage.top.get(well_known_keys::CODE).cloned()
+			.ok_or("The state has no runtime code")?;
+		// Hooks that read `:code` must see the candidate.
+		storage.top.insert(well_known_keys::CODE.to_vec(), candidate.clone());
+
+		let mut ext = sp_io::TestExternalities::new(storage);
+		let mut call = |code: &[u8], function: &str| {
+			sc_executor::call_in_wasm::<sp_io::SubstrateHostFunctions>(
+				function, &[], method, &mut ext.ext(), code, heap_pages, true,
+			).map_err(|e| format!("Error calling {}: {}", function, e))
+		};
+		let version = |encoded: Vec<u8>| RuntimeVersion::decode(&mut &encoded[..])
+			.map_err(|e| format!("Error decoding the runtime version: {}", e));
+
+		let current_version = version(call(&current, "Core_version")?)?;
+		let candidate_version = version(call(&candidate, "Core_version")?)?;
+		println!(
+			"Upgrading {} from spec version {} to {}",
+			candidate_version.spec_name, current_version.spec_version, candidate_version.spec_version,
+		);
+		if candidate_version.spec_version <= current_version.spec_version {
+			println!("Warning: the spec version must increase, or the upgrade hooks won't run on chain");
+		}
+
+		let encoded = call(&candidate, "TryUpgradeApi_try_upgrade")
+			.map_err(|e| format!("{}, is the candidate built from this node's runtime?", e))?;
+		let report = UpgradeReport::decode(&mut &encoded[..])
+			.map_err(|e| format!("Error decoding the upgrade report: {}", e))?;
+
+		println!("on_runtime_upgrade weight: {}", report.weight);
+		for violation in &report.violations {
+			println!("Violation: {:?}", violation);
+		}
+		if report.violations.is_empty() {
+			println!("All invariants hold");
+			Ok(())
+		} else {
+			Err(format!("{} invariant violations", report.violations.len()).into())
+		}
+	}
+}
+
+impl CliConfiguration for TryUpgradeCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
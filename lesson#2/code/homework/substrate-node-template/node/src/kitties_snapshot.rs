@@ -0,0 +1,223 @@
+//! Domain-level snapshots of the kitties pallet.
+//!
+//! `export-kitties` reads them from the client's state, and `generate-spec` can load them back
+//! into the kitties genesis config to fork a collection into a fresh chain.
+
+use std::{fmt::Write, path::Path, str::FromStr};
+
+use codec::Decode;
+use frame_support::{StorageMap, StorageValue};
+use node_template_runtime::{
+	opaque::Block, pallet_kitties::{self, Kitty, KittyRelation}, AccountId, KittyIndex, Runtime,
+};
+use sc_client_api::{Backend, StorageProvider};
+use serde::{Deserialize, Serialize};
+use sp_core::{crypto::Ss58Codec, storage::StorageKey};
+use sp_runtime::generic::BlockId;
+
+/// A kitty as exported. Accounts are SS58 addresses and the dna is hex encoded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KittySnapshot {
+	pub id: KittyIndex,
+	pub owner: String,
+	pub dna: String,
+	pub father: Option<KittyIndex>,
+	pub mother: Option<KittyIndex>,
+	/// The id of the lock holding the owner's deposit, informational only.
+	pub lock_id: Option<u32>,
+}
+
+/// All kitties at a block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KittiesSnapshot {
+	pub block_hash: String,
+	pub kitties: Vec<KittySnapshot>,
+}
+
+/// The file format of a snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotFormat {
+	Json,
+	/// One kitty per line with the header `id,owner,dna,father,mother,lockId`.
+	Csv,
+}
+
+impl FromStr for SnapshotFormat {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"json" => Ok(SnapshotFormat::Json),
+			"csv" => Ok(SnapshotFormat::Csv),
+			_ => Err(format!("Unknown snapshot format {}, expected json or csv", s)),
+		}
+	}
+}
+
+impl SnapshotFormat {
+	/// CSV if `path` ends with `.csv`, JSON otherwise.
+	pub fn of_path(path: &Path) -> Self {
+		if path.extension().map_or(false, |ext| ext == "csv") {
+			SnapshotFormat::Csv
+		} else {
+			SnapshotFormat::Json
+		}
+	}
+}
+
+const CSV_HEADER: &str = "id,owner,dna,father,mother,lockId";
+
+fn read_storage<C, BE, T: Decode>(client: &C, at: &BlockId<Block>, key: Vec<u8>) -> Result<Option<T>, String>
+where
+	C: StorageProvider<Block, BE>,
+	BE: Backend<Block>,
+{
+	client.storage(at, &StorageKey(key))
+		.map_err(|e| format!("Error reading state: {}", e))?
+		.map(|data| T::decode(&mut &data.0[..]).map_err(|e| format!("Error decoding state: {}", e)))
+		.transpose()
+}
+
+/// Read every kitty at block `at`.
+pub fn export<C, BE>(client: &C, at: BlockId<Block>, block_hash: String) -> Result<KittiesSnapshot, String>
+where
+	C: StorageProvider<Block, BE>,
+	BE: Backend<Block>,
+{
+	type Kitties = pallet_kitties::Kitties<Runtime>;
+	type KittyOwners = pallet_kitties::KittyOwners<Runtime>;
+	type KittyRelations = pallet_kitties::KittyRelations<Runtime>;
+	type LockId = pallet_kitties::LockId<Runtime>;
+
+	let count: KittyIndex = read_storage(client, &at, pallet_kitties::KittiesCount::<Runtime>::hashed_key().to_vec())?
+		.unwrap_or_default();
+
+	let mut kitties = Vec::new();
+	for id in 0..count {
+		// Ids are never reused, a missing kitty would mean corrupted state.
+		let kitty: Kitty = read_storage(client, &at, Kitties::hashed_key_for(id))?
+			.ok_or_else(|| format!("Kitty {} is missing", id))?;
+		let owner: AccountId = read_storage(client, &at, KittyOwners::hashed_key_for(id))?
+			.ok_or_else(|| format!("Kitty {} has no owner", id))?;
+		let relation: KittyRelation<KittyIndex> = read_storage(client, &at, KittyRelations::hashed_key_for(id))?
+			.unwrap_or_default();
+		let lock_id: Option<u32> = read_storage(client, &at, LockId::hashed_key_for(id))?;
+		// Breeding requires different parents, equal ones are the default of a created kitty.
+		let parents = relation.father != relation.mother;
+
+		kitties.push(KittySnapshot {
+			id,
+			owner: owner.to_ss58check(),
+			dna: format!("0x{}", hex::encode(kitty.0)),
+			father: if parents { Some(relation.father) } else { None },
+			mother: if parents { Some(relation.mother) } else { None },
+			lock_id,
+		});
+	}
+
+	Ok(KittiesSnapshot { block_hash, kitties })
+}
+
+fn optional<T: ToString>(value: &Option<T>) -> String {
+	value.as_ref().map(ToString::to_string).unwrap_or_default()
+}
+
+impl KittiesSnapshot {
+	/// Render the snapshot. CSV only has the kitties.
+	pub fn to_string(&self, format: SnapshotFormat) -> Result<String, String> {
+		match format {
+			SnapshotFormat::Json => serde_json::to_string_pretty(self).map_err(|e| e.to_string()),
+			SnapshotFormat::Csv => {
+				let mut csv = format!("{}\n", CSV_HEADER);
+				for k in &self.kitties {
+					writeln!(
+						csv, "{},{},{},{},{},{}",
+						k.id, k.owner, k.dna, optional(&k.father), optional(&k.mother), optional(&k.lock_id),
+					).expect("writing to a String can't fail; qed");
+				}
+				Ok(csv)
+			},
+		}
+	}
+}
+
+fn parse_csv(content: &str) -> Result<Vec<KittySnapshot>, String> {
+	fn field<T: FromStr>(value: &str, line: usize) -> Result<Option<T>, String> {
+		if value.is_empty() {
+			return Ok(None);
+		}
+		value.parse().map(Some).map_err(|_| format!("Invalid value {} on line {}", value, line))
+	}
+
+	let mut lines = content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+	match lines.next() {
+		Some((_, header)) if header.trim() == CSV_HEADER => (),
+		_ => return Err(format!("Expected the header {}", CSV_HEADER)),
+	}
+	lines.map(|(n, line)| {
+		let line_number = n + 1;
+		let values: Vec<&str> = line.split(',').map(str::trim).collect();
+		if values.len() != 6 {
+			return Err(format!("Expected 6 values on line {}", line_number));
+		}
+		Ok(KittySnapshot {
+			id: field(values[0], line_number)?.ok_or_else(|| format!("Missing id on line {}", line_number))?,
+			owner: values[1].into(),
+			dna: values[2].into(),
+			father: field(values[3], line_number)?,
+			mother: field(values[4], line_number)?,
+			lock_id: field(values[5], line_number)?,
+		})
+	}).collect()
+}
+
+/// Load the kitties of a snapshot written by `export-kitties`, in either format.
+pub fn load(path: &Path) -> Result<Vec<KittySnapshot>, String> {
+	let content = std::fs::read_to_string(path)
+		.map_err(|e| format!("Error reading {}: {}", path.display(), e))?;
+	match SnapshotFormat::of_path(path) {
+		SnapshotFormat::Json => serde_json::from_str::<KittiesSnapshot>(&content)
+			.map(|snapshot| snapshot.kitties)
+			.map_err(|e| format!("Error parsing {}: {}", path.display(), e)),
+		SnapshotFormat::Csv => parse_csv(&content)
+			.map_err(|e| format!("Error parsing {}: {}", path.display(), e)),
+	}
+}
+
+/// The kitties genesis config of a snapshot, `(kitties, relations)`.
+///
+/// Genesis kitties get consecutive ids, so the snapshot must hold every id from 0 to keep
+/// ids and relations intact. Parents must be two different kitties of the snapshot.
+pub fn into_genesis(
+	mut kitties: Vec<KittySnapshot>,
+	parse_account: impl Fn(&str) -> Result<AccountId, String>,
+	parse_dna: impl Fn(&str) -> Result<[u8; 16], String>,
+) -> Result<(Vec<(AccountId, [u8; 16])>, Vec<(KittyIndex, KittyIndex, KittyIndex)>), String> {
+	kitties.sort_by_key(|k| k.id);
+
+	let mut genesis_kitties = Vec::with_capacity(kitties.len());
+	let mut relations = Vec::new();
+	for (expected, k) in kitties.iter().enumerate() {
+		if k.id as usize != expected {
+			return Err(format!("Snapshot kitty ids must be 0, 1, 2, ..., found {} at {}", k.id, expected));
+		}
+		genesis_kitties.push((parse_account(&k.owner)?, parse_dna(&k.dna)?));
+		match (k.father, k.mother) {
+			(Some(father), Some(mother)) => {
+				if father == mother {
+					return Err(format!("Kitty {} has the same father and mother {}", k.id, father));
+				}
+				if let Some(parent) = [father, mother].iter().find(|p| **p as usize >= kitties.len()) {
+					return Err(format!("Parent {} of kitty {} is not in the snapshot", parent, k.id));
+				}
+				relations.push((k.id, father, mother))
+			},
+			(None, None) => (),
+			_ => return Err(format!("Kitty {} has only one parent", k.id)),
+		}
+	}
+	Ok((genesis_kitties, relations))
+}
+
@@ -6,6 +6,7 @@ mod chain_spec;
 mod service;
 mod cli;
 mod command;
+mod kitties_snapshot;
 mod rpc;
 
 fn main() -> sc_cli::Result<()> {
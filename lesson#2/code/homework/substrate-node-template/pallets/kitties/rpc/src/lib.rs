@@ -0,0 +1,150 @@
+//! RPC methods to query pallet-kitties.
+//!
+//! Kitty details are read through the `KittiesApi` runtime API. The history is read from the
+//! offchain index and stays empty unless the node runs with `--enable-offchain-indexing true`.
+
+use std::sync::Arc;
+
+use codec::{Codec, Decode};
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use pallet_kitties::{history_key, KittyHistoryRecord};
+use parking_lot::RwLock;
+use sc_rpc_api::DenyUnsafe;
+use serde::{de::DeserializeOwned, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::offchain::OffchainStorage;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+pub use pallet_kitties_runtime_api::{KittiesApi as KittiesRuntimeApi, KittyGenome, KittyInfo, Violation};
+
+/// Most history records returned for one kitty.
+pub const MAX_HISTORY_LEN: u32 = 1024;
+
+#[rpc]
+pub trait KittiesApi<BlockHash, AccountId, KittyIndex, BlockNumber> {
+	/// Details of a kitty, `null` if it does not exist.
+	#[rpc(name = "kitties_kitty")]
+	fn kitty(&self, kitty_id: KittyIndex, at: Option<BlockHash>) -> Result<Option<KittyInfo<AccountId, KittyIndex>>>;
+
+	/// The owner of a kitty.
+	#[rpc(name = "kitties_owner")]
+	fn owner(&self, kitty_id: KittyIndex, at: Option<BlockHash>) -> Result<Option<AccountId>>;
+
+	/// The decoded looks of a kitty.
+	#[rpc(name = "kitties_genome")]
+	fn genome(&self, kitty_id: KittyIndex, at: Option<BlockHash>) -> Result<Option<KittyGenome>>;
+
+	/// The kitties owned by `owner`.
+	#[rpc(name = "kitties_kittiesOf")]
+	fn kitties_of(&self, owner: AccountId, at: Option<BlockHash>) -> Result<Vec<KittyIndex>>;
+
+	/// Number of kitties ever created.
+	#[rpc(name = "kitties_count")]
+	fn count(&self, at: Option<BlockHash>) -> Result<KittyIndex>;
+
+	/// The broken storage invariants, empty if the kitties storage is consistent.
+	///
+	/// Reads the whole kitties storage, so it is slow on big collections and only allowed
+	/// when unsafe RPC methods are.
+	#[rpc(name = "kitties_tryState")]
+	fn try_state(&self, at: Option<BlockHash>) -> Result<Vec<Violation<AccountId, KittyIndex>>>;
+
+	/// What happened to `kitty_id`, oldest first.
+	#[rpc(name = "kitties_history")]
+	fn history(&self, kitty_id: KittyIndex) -> Result<Vec<KittyHistoryRecord<AccountId, KittyIndex, BlockNumber>>>;
+}
+
+/// Serves `KittiesApi` from the runtime and the offchain database of the node.
+pub struct Kitties<C, S> {
+	client: Arc<C>,
+	storage: Option<Arc<RwLock<S>>>,
+	deny_unsafe: DenyUnsafe,
+}
+
+impl<C, S> Kitties<C, S> {
+	/// `storage` is the offchain database, the history is unavailable without it.
+	pub fn new(client: Arc<C>, storage: Option<S>, deny_unsafe: DenyUnsafe) -> Self {
+		Kitties { client, storage: storage.map(|s| Arc::new(RwLock::new(s))), deny_unsafe }
+	}
+}
+
+/// Error code of failed runtime API calls.
+const RUNTIME_ERROR: i64 = 1;
+/// Error code of history queries on a node without offchain storage.
+const OFFCHAIN_UNAVAILABLE: i64 = 2;
+
+fn runtime_error(e: impl std::fmt::Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(RUNTIME_ERROR),
+		message: "Unable to query kitties.".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+impl<C, S, Block, AccountId, KittyIndex, BlockNumber> KittiesApi<<Block as BlockT>::Hash, AccountId, KittyIndex, BlockNumber>
+	for Kitties<C, S>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: KittiesRuntimeApi<Block, AccountId, KittyIndex>,
+	S: OffchainStorage + 'static,
+	AccountId: Codec + Serialize + DeserializeOwned + Send + Sync + 'static,
+	KittyIndex: Codec + Serialize + DeserializeOwned + Send + Sync + 'static,
+	BlockNumber: Codec + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+	fn kitty(&self, kitty_id: KittyIndex, at: Option<<Block as BlockT>::Hash>) -> Result<Option<KittyInfo<AccountId, KittyIndex>>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		self.client.runtime_api().kitty(&at, kitty_id).map_err(runtime_error)
+	}
+
+	fn owner(&self, kitty_id: KittyIndex, at: Option<<Block as BlockT>::Hash>) -> Result<Option<AccountId>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		self.client.runtime_api().owner(&at, kitty_id).map_err(runtime_error)
+	}
+
+	fn genome(&self, kitty_id: KittyIndex, at: Option<<Block as BlockT>::Hash>) -> Result<Option<KittyGenome>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		self.client.runtime_api().genome(&at, kitty_id).map_err(runtime_error)
+	}
+
+	fn kitties_of(&self, owner: AccountId, at: Option<<Block as BlockT>::Hash>) -> Result<Vec<KittyIndex>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		self.client.runtime_api().kitties_of(&at, owner).map_err(runtime_error)
+	}
+
+	fn count(&self, at: Option<<Block as BlockT>::Hash>) -> Result<KittyIndex> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		self.client.runtime_api().kitties_count(&at).map_err(runtime_error)
+	}
+
+	fn try_state(&self, at: Option<<Block as BlockT>::Hash>) -> Result<Vec<Violation<AccountId, KittyIndex>>> {
+		self.deny_unsafe.check_if_safe()?;
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		self.client.runtime_api().try_state(&at).map_err(runtime_error)
+	}
+
+	fn history(&self, kitty_id: KittyIndex) -> Result<Vec<KittyHistoryRecord<AccountId, KittyIndex, BlockNumber>>> {
+		let storage = self.storage.as_ref().ok_or_else(|| RpcError {
+			code: ErrorCode::ServerError(OFFCHAIN_UNAVAILABLE),
+			message: "The node has no offchain storage.".into(),
+			data: None,
+		})?;
+		let storage = storage.read();
+		let mut records = Vec::new();
+		for n in 0..MAX_HISTORY_LEN {
+			let raw = match storage.get(sp_offchain::STORAGE_PREFIX, &history_key(&kitty_id, n)) {
+				Some(raw) => raw,
+				None => break,
+			};
+			let record = Decode::decode(&mut &raw[..]).map_err(|e| RpcError {
+				code: ErrorCode::InternalError,
+				message: "Unable to decode kitty history record.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})?;
+			records.push(record);
+		}
+		Ok(records)
+	}
+}
@@ -0,0 +1,28 @@
+//! Runtime API definition for pallet-kitties.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::vec::Vec;
+
+pub use pallet_kitties::{KittyGenome, KittyInfo, Violation};
+
+sp_api::decl_runtime_apis! {
+    pub trait KittiesApi<AccountId, KittyIndex> where
+        AccountId: Codec,
+        KittyIndex: Codec,
+    {
+        /// Details of a kitty, `None` if it does not exist.
+        fn kitty(kitty_id: KittyIndex) -> Option<KittyInfo<AccountId, KittyIndex>>;
+        /// The owner of a kitty.
+        fn owner(kitty_id: KittyIndex) -> Option<AccountId>;
+        /// The decoded looks of a kitty.
+        fn genome(kitty_id: KittyIndex) -> Option<KittyGenome>;
+        /// The kitties owned by `owner`.
+        fn kitties_of(owner: AccountId) -> Vec<KittyIndex>;
+        /// Number of kitties ever created.
+        fn kitties_count() -> KittyIndex;
+        /// The broken storage invariants, empty if the storage is consistent. Reads all of it.
+        fn try_state() -> Vec<Violation<AccountId, KittyIndex>>;
+    }
+}
@@ -3,13 +3,20 @@
 use codec::{Decode, Encode};
 use frame_support::{
     decl_error, decl_event, decl_module, decl_storage, dispatch, ensure,
-    sp_runtime::traits::AtLeast32BitUnsigned,
-    traits::{Currency, Get, LockIdentifier, LockableCurrency, Randomness, WithdrawReasons},
-    Parameter, StorageMap,
+    sp_runtime::traits::{AtLeast32BitUnsigned, One, Zero},
+    traits::{
+        Currency, ExistenceRequirement, Get, LockIdentifier, LockableCurrency, OnUnbalanced,
+        Randomness, WithdrawReason, WithdrawReasons,
+    },
+    transactional,
+    weights::Weight,
+    IterableStorageMap, Parameter, StorageMap, StorageValue,
 };
 use frame_system::ensure_signed;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
 use sp_io::hashing::blake2_128;
-use sp_runtime::{traits::Bounded, DispatchError};
+use sp_runtime::{traits::Bounded, DispatchError, RuntimeDebug};
 use sp_std::vec::Vec;
 
 #[cfg(test)]
@@ -24,11 +31,73 @@ mod tests_transfer;
 #[cfg(test)]
 mod tests_breed;
 
+#[cfg(test)]
+mod tests_upgrade;
+
+#[cfg(test)]
+mod tests_try_state;
+
+#[cfg(test)]
+mod tests_proptest;
+
+#[cfg(test)]
+mod tests_free_operations;
+
+#[cfg(test)]
+mod tests_breed_fee;
+
 const BASE_KITTY_ID: LockIdentifier = [66, 0, 0, 0, 0, 0, 0, 0];
 
+//当前的存储版本，存储结构变化时加1并在on_runtime_upgrade中迁移
+pub const STORAGE_VERSION: u32 = 2;
+
 #[derive(Encode, Decode)]
 pub struct Kitty(pub [u8; 16]);
 
+//各部位的样式数量，与前端的头像素材一致
+pub const BODY_VARIANTS: u8 = 15;
+pub const EYES_VARIANTS: u8 = 15;
+pub const ACCESSORY_VARIANTS: u8 = 20;
+pub const FUR_VARIANTS: u8 = 10;
+pub const MOUTH_VARIANTS: u8 = 10;
+
+/// The looks of a kitty, each field is a 0-based variant index.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct KittyGenome {
+    pub body: u8,
+    pub eyes: u8,
+    pub accessory: u8,
+    pub fur: u8,
+    pub mouth: u8,
+}
+
+impl Kitty {
+    //由dna的前5个字节解出各部位的样式
+    pub fn genome(&self) -> KittyGenome {
+        KittyGenome {
+            body: self.0[0] % BODY_VARIANTS,
+            eyes: self.0[1] % EYES_VARIANTS,
+            accessory: self.0[2] % ACCESSORY_VARIANTS,
+            fur: self.0[3] % FUR_VARIANTS,
+            mouth: self.0[4] % MOUTH_VARIANTS,
+        }
+    }
+}
+
+/// Everything a client shows about a kitty.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct KittyInfo<AccountId, KittyIndex> {
+    pub id: KittyIndex,
+    pub owner: AccountId,
+    pub dna: [u8; 16],
+    pub genome: KittyGenome,
+    /// `(father, mother)`, `None` for created kitties.
+    pub parents: Option<(KittyIndex, KittyIndex)>,
+    pub children: Vec<KittyIndex>,
+}
+
 #[derive(Default, Encode, Decode, Clone)]
 pub struct KittyRelation<KittyIndex> {
     pub father: KittyIndex,
@@ -42,8 +111,70 @@ pub struct KittyBreed<KittyIndex> {
     pub children: KittyIndex,
 }
 
+/// Prefix of the offchain index keys of kitty history records.
+pub const HISTORY_INDEX_PREFIX: &[u8] = b"kitties::history";
+
+/// What happened to a kitty.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum KittyAction<AccountId, KittyIndex> {
+    Created { owner: AccountId },
+    Transferred { from: AccountId, to: AccountId },
+    /// The kitty was born from `father` and `mother`.
+    Bred { owner: AccountId, father: KittyIndex, mother: KittyIndex },
+    /// The kitty got a `child` with `partner`.
+    ParentOf { child: KittyIndex, partner: KittyIndex },
+}
+
+/// A history record written to the offchain index, so clients don't need to scan events.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct KittyHistoryRecord<AccountId, KittyIndex, BlockNumber> {
+    pub block: BlockNumber,
+    pub action: KittyAction<AccountId, KittyIndex>,
+}
+
+/// A broken invariant of the kitties storage, see `Module::do_try_state`.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum Violation<AccountId, KittyIndex> {
+    /// `KittiesCount` doesn't match the number of kitties, so an id is missing or reused.
+    CountMismatch { count: KittyIndex, kitties: u32 },
+    /// A kitty id is not below `KittiesCount`.
+    BeyondCount { kitty: KittyIndex },
+    /// A map has an entry for a kitty that is not in `Kitties`.
+    UnknownKitty { kitty: KittyIndex },
+    /// A kitty has no `KittyOwners` entry.
+    MissingOwner { kitty: KittyIndex },
+    /// `KittyOwners` says `owner` owns the kitty, but it is missing from their `UserKitties`.
+    NotInUserKitties { kitty: KittyIndex, owner: AccountId },
+    /// The kitty is in `account`'s `UserKitties`, but `KittyOwners` says it belongs to `owner`.
+    WrongOwner { kitty: KittyIndex, account: AccountId, owner: Option<AccountId> },
+    /// The kitty is listed more than once in `account`'s `UserKitties`.
+    DuplicateInUserKitties { kitty: KittyIndex, account: AccountId },
+    /// A parent of the kitty doesn't exist, or doesn't list the kitty as its child.
+    ParentMismatch { kitty: KittyIndex, parent: KittyIndex },
+    /// A child listed by the kitty doesn't exist, or doesn't have the kitty and `breed` as parents.
+    ChildMismatch { kitty: KittyIndex, child: KittyIndex },
+    /// A kitty has no `LockId` or `LockedAmount` entry.
+    MissingLock { kitty: KittyIndex },
+    /// The lock id of the kitty is not below `LockIndexd`.
+    LockBeyondIndex { kitty: KittyIndex, lock_id: u32 },
+    /// Another kitty has the same lock id.
+    SharedLock { kitty: KittyIndex, lock_id: u32 },
+    /// The owner's balance is not locked for the kitty.
+    OwnerNotLocked { kitty: KittyIndex, owner: AccountId },
+}
+
+/// Offchain index key of the `n`th history record of `kitty_id`.
+pub fn history_key<KittyIndex: Encode>(kitty_id: &KittyIndex, n: u32) -> Vec<u8> {
+    (HISTORY_INDEX_PREFIX, kitty_id, n).encode()
+}
+
 pub type BalanceOf<T> =
     <<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+pub type NegativeImbalanceOf<T> =
+    <<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::NegativeImbalance;
 
 pub trait Trait: frame_system::Trait {
     type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
@@ -51,6 +182,14 @@ pub trait Trait: frame_system::Trait {
     type KittyIndex: Parameter + AtLeast32BitUnsigned + Bounded + Default + Copy;
     type Currency: LockableCurrency<Self::AccountId>;
     type KittyLockToken: Get<BalanceOf<Self>>;
+    /// Kitty operations each account may do without fees per period, see `use_free_operation`.
+    type FreeOperations: Get<u32>;
+    /// Length of a free operations period in blocks, zero for a single period that never ends.
+    type FreeOperationsPeriod: Get<Self::BlockNumber>;
+    /// Paid by the owner for every bred kitty, on top of the lock.
+    type BreedFee: Get<BalanceOf<Self>>;
+    /// Receives the breeding fees, e.g. a treasury.
+    type OnFee: OnUnbalanced<NegativeImbalanceOf<Self>>;
 }
 
 decl_storage! {
@@ -62,6 +201,32 @@ decl_storage! {
         pub KittyRelations get(fn kitty_relations): map hasher(blake2_128_concat) T::KittyIndex => KittyRelation<T::KittyIndex>;
         pub LockIndexd get(fn lock_index): u32;
         pub LockId get(fn lock_id): map hasher(blake2_128_concat) T::KittyIndex => u32;
+        //每个kitty质押时的KittyLockToken，KittyLockToken修改后已有的质押不变
+        pub LockedAmount get(fn locked_amount): map hasher(blake2_128_concat) T::KittyIndex => BalanceOf<T>;
+        //每个kitty已写入offchain index的历史记录数
+        pub KittyHistoryLen get(fn kitty_history_len): map hasher(blake2_128_concat) T::KittyIndex => u32;
+        //账户在某个周期内已使用的免费操作数，(周期, 数量)
+        pub FreeOperationsUsed get(fn free_operations_used): map hasher(blake2_128_concat) T::AccountId => (T::BlockNumber, u32);
+        //存储版本，新链从当前版本开始
+        pub StorageVersion get(fn storage_version) build(|_| STORAGE_VERSION): u32;
+    }
+    add_extra_genesis {
+        //创世kitty，(owner, dna)，owner需在balances中有足够的余额
+        config(kitties): Vec<(T::AccountId, [u8; 16])>;
+        //创世kitty的关系，(child, father, mother)，下标为kitties中的顺序
+        config(relations): Vec<(T::KittyIndex, T::KittyIndex, T::KittyIndex)>;
+        build(|config: &GenesisConfig<T>| {
+            for (owner, dna) in &config.kitties {
+                Module::<T>::genesis_kitty(owner, *dna);
+            }
+            for (child, father, mother) in &config.relations {
+                for id in &[child, father, mother] {
+                    assert!(Module::<T>::kitties(*id).is_some(), "genesis relation of an unknown kitty");
+                }
+                assert!(father != mother, "genesis relation requires different parents");
+                Module::<T>::new_kitty_relation(*child, *father, *mother);
+            }
+        });
     }
 }
 
@@ -102,10 +267,30 @@ decl_module! {
 
         const KittyLockToken: BalanceOf<T> = T::KittyLockToken::get();
 
+        const FreeOperations: u32 = T::FreeOperations::get();
+
+        const FreeOperationsPeriod: T::BlockNumber = T::FreeOperationsPeriod::get();
+
+        const BreedFee: BalanceOf<T> = T::BreedFee::get();
+
         type Error = Error<T>;
 
         fn deposit_event() = default;
 
+        fn on_runtime_upgrade() -> Weight {
+            //版本0的存储与版本1相同，只需记录版本号；版本2新增LockedAmount
+            let version = Self::storage_version();
+            if version >= STORAGE_VERSION {
+                return T::DbWeight::get().reads(1);
+            }
+            let mut migrated = 0;
+            if version < 2 {
+                migrated = Self::migrate_locked_amount();
+            }
+            StorageVersion::put(STORAGE_VERSION);
+            T::DbWeight::get().reads_writes(1 + migrated, 1 + migrated)
+        }
+
         #[weight = 0]
         pub fn create(origin) -> dispatch::DispatchResult {
             let sender = ensure_signed(origin)?;
@@ -119,6 +304,7 @@ decl_module! {
             let kitty = Kitty(dna);
             Self::insert_kitty(&sender, kitty_id, kitty);
             Self::set_lock_index(lock_id + 1);
+            Self::index_history(kitty_id, KittyAction::Created { owner: sender.clone() });
             Self::deposit_event(RawEvent::Created(sender,kitty_id));
             Ok(())
         }
@@ -129,15 +315,19 @@ decl_module! {
             Self::check_balance(&to)?;
             Self::transfer_kitty(&sender,&to,kitty_index)?;
             Self::transfer_lock(&sender,&to,kitty_index);
+            Self::index_history(kitty_index, KittyAction::Transferred { from: sender.clone(), to: to.clone() });
             Self::deposit_event(RawEvent::Transferred(sender, to, kitty_index));
             Ok(())
         }
 
         #[weight = 0]
+        #[transactional]
         pub fn breed(origin, kitty_id_1: T::KittyIndex, kitty_id_2: T::KittyIndex) -> dispatch::DispatchResult {
             let sender = ensure_signed(origin)?;
             let lock_id: u32 = Self::next_lock_index()?;
             // ensure!(1==2,Error::<T>::FreeNotEnough);
+            //支付繁殖费，失败时整个调用回滚
+            Self::pay_breed_fee(&sender)?;
             //检查余额
             Self::check_balance(&sender)?;
 
@@ -153,6 +343,181 @@ decl_module! {
 }
 
 impl<T: Trait> Module<T> {
+    //kitty的完整信息，供runtime api使用
+    pub fn kitty_info(kitty_id: T::KittyIndex) -> Option<KittyInfo<T::AccountId, T::KittyIndex>> {
+        let kitty = Self::kitties(kitty_id)?;
+        let owner = Self::kitty_owners(kitty_id)?;
+        let relation = Self::kitty_relations(kitty_id);
+        //繁殖要求父母不同，父母相同说明只是默认值
+        let parents = if relation.father != relation.mother {
+            Some((relation.father, relation.mother))
+        } else {
+            None
+        };
+        Some(KittyInfo {
+            id: kitty_id,
+            owner,
+            genome: kitty.genome(),
+            dna: kitty.0,
+            parents,
+            children: relation.children.into_iter().map(|c| c.children).collect(),
+        })
+    }
+
+    //当前免费操作的周期
+    fn free_operations_period() -> T::BlockNumber {
+        let period = T::FreeOperationsPeriod::get();
+        if period.is_zero() {
+            Zero::zero()
+        } else {
+            <frame_system::Module<T>>::block_number() / period
+        }
+    }
+
+    //本周期内剩余的免费操作数
+    pub fn free_operations_left(who: &T::AccountId) -> u32 {
+        let (period, used) = Self::free_operations_used(who);
+        let used = if period == Self::free_operations_period() { used } else { 0 };
+        T::FreeOperations::get().saturating_sub(used)
+    }
+
+    //使用一次免费操作，没有剩余时返回false。由runtime的交易费扩展调用，调用失败也会用掉
+    pub fn use_free_operation(who: &T::AccountId) -> bool {
+        let left = Self::free_operations_left(who);
+        if left == 0 {
+            return false;
+        }
+        let used = T::FreeOperations::get() - left + 1;
+        <FreeOperationsUsed<T>>::insert(who, (Self::free_operations_period(), used));
+        true
+    }
+
+    //升级后检查存储的一致性，返回所有被破坏的约束
+    pub fn post_upgrade() -> Vec<Violation<T::AccountId, T::KittyIndex>> {
+        Self::do_try_state()
+    }
+
+    //检查六个存储之间的约束，返回所有被破坏的约束。会遍历全部存储，只用于测试和节点的检查
+    pub fn do_try_state() -> Vec<Violation<T::AccountId, T::KittyIndex>> {
+        let mut violations = Vec::new();
+        let count = Self::kitties_count();
+        let lock_index = Self::lock_index();
+
+        //kitty的下标从0连续分配，且从不删除
+        let mut kitties = 0u32;
+        let mut lock_owners = sp_std::collections::btree_map::BTreeMap::new();
+        for (kitty, _) in <Kitties<T>>::iter() {
+            kitties += 1;
+            if kitty >= count {
+                violations.push(Violation::BeyondCount { kitty });
+            }
+            let owner = match Self::kitty_owners(kitty) {
+                Some(owner) => owner,
+                None => {
+                    violations.push(Violation::MissingOwner { kitty });
+                    continue;
+                }
+            };
+
+            //质押
+            if !<LockId<T>>::contains_key(kitty) || !<LockedAmount<T>>::contains_key(kitty) {
+                violations.push(Violation::MissingLock { kitty });
+                continue;
+            }
+            let lock_id = Self::lock_id(kitty);
+            if lock_id >= lock_index {
+                violations.push(Violation::LockBeyondIndex { kitty, lock_id });
+            }
+            if lock_owners.insert(lock_id, kitty).is_some() {
+                violations.push(Violation::SharedLock { kitty, lock_id });
+            }
+            let lock = Self::locked_amount(kitty);
+            //有质押时，余额不能降到质押以下
+            if !lock.is_zero()
+                && T::Currency::ensure_can_withdraw(
+                    &owner,
+                    Zero::zero(),
+                    WithdrawReasons::all(),
+                    lock - One::one(),
+                )
+                .is_ok()
+            {
+                violations.push(Violation::OwnerNotLocked { kitty, owner });
+            }
+        }
+        if T::KittyIndex::from(kitties) != count {
+            violations.push(Violation::CountMismatch { count, kitties });
+        }
+
+        //其他存储不能引用不存在的kitty
+        let unknown = <KittyOwners<T>>::iter()
+            .map(|(kitty, _)| kitty)
+            .chain(<KittyRelations<T>>::iter().map(|(kitty, _)| kitty))
+            .chain(<LockId<T>>::iter().map(|(kitty, _)| kitty))
+            .chain(<LockedAmount<T>>::iter().map(|(kitty, _)| kitty))
+            .filter(|kitty| !<Kitties<T>>::contains_key(kitty));
+        for kitty in unknown {
+            violations.push(Violation::UnknownKitty { kitty });
+        }
+
+        //owner与UserKitties一致
+        for (kitty, owner) in <KittyOwners<T>>::iter() {
+            if !Self::user_kitties(&owner).contains(&kitty) {
+                violations.push(Violation::NotInUserKitties { kitty, owner });
+            }
+        }
+        for (account, kitties) in <UserKitties<T>>::iter() {
+            for (i, kitty) in kitties.iter().enumerate() {
+                if kitties[..i].contains(kitty) {
+                    violations.push(Violation::DuplicateInUserKitties { kitty: *kitty, account: account.clone() });
+                    continue;
+                }
+                let owner = Self::kitty_owners(kitty);
+                if owner.as_ref() != Some(&account) {
+                    violations.push(Violation::WrongOwner { kitty: *kitty, account: account.clone(), owner });
+                }
+            }
+        }
+
+        //父母与孩子互相引用
+        for (kitty, relation) in <KittyRelations<T>>::iter() {
+            if relation.father != relation.mother {
+                for (parent, partner) in &[(relation.father, relation.mother), (relation.mother, relation.father)] {
+                    let listed = <Kitties<T>>::contains_key(parent)
+                        && Self::kitty_relations(parent).children.iter()
+                            .any(|c| c.children == kitty && c.breed == *partner);
+                    if !listed {
+                        violations.push(Violation::ParentMismatch { kitty, parent: *parent });
+                    }
+                }
+            }
+            for child in &relation.children {
+                let child_relation = Self::kitty_relations(child.children);
+                let parents = (child_relation.father, child_relation.mother);
+                let matches = <Kitties<T>>::contains_key(child.children)
+                    && (parents == (kitty, child.breed) || parents == (child.breed, kitty));
+                if !matches {
+                    violations.push(Violation::ChildMismatch { kitty, child: child.children });
+                }
+            }
+        }
+        violations
+    }
+
+    pub fn kitty_genome(kitty_id: T::KittyIndex) -> Option<KittyGenome> {
+        Self::kitties(kitty_id).map(|kitty| kitty.genome())
+    }
+
+    //创建创世kitty，与create相同但使用给定的dna。创世时没有offchain index，不写历史记录
+    fn genesis_kitty(owner: &T::AccountId, dna: [u8; 16]) {
+        let kitty_id = Self::next_kitty_id().expect("too many genesis kitties");
+        let lock_id = Self::next_lock_index().expect("too many genesis kitties");
+        Self::check_balance(owner).expect("genesis kitty owner can't afford the lock");
+        Self::lock_token(owner, kitty_id, lock_id);
+        Self::insert_kitty(owner, kitty_id, Kitty(dna));
+        Self::set_lock_index(lock_id + 1);
+    }
+
     //插入kitty
     fn insert_kitty(owner: &T::AccountId, kitty_id: T::KittyIndex, kitty: Kitty) {
         <Kitties<T>>::insert(kitty_id, kitty);
@@ -223,6 +588,23 @@ impl<T: Trait> Module<T> {
         Ok(())
     }
 
+    //扣除繁殖费并交给OnFee，扣除后余额需仍能覆盖已有的质押
+    fn pay_breed_fee(sender: &T::AccountId) -> dispatch::DispatchResult {
+        let fee = T::BreedFee::get();
+        if fee.is_zero() {
+            return Ok(());
+        }
+        let imbalance = T::Currency::withdraw(
+            sender,
+            fee,
+            WithdrawReason::Fee.into(),
+            ExistenceRequirement::KeepAlive,
+        )
+        .map_err(|_| Error::<T>::FreeNotEnough)?;
+        T::OnFee::on_unbalanced(imbalance);
+        Ok(())
+    }
+
     //获取一个随机dna
     fn random_value(sender: &T::AccountId) -> [u8; 16] {
         let payload = (
@@ -261,6 +643,14 @@ impl<T: Trait> Module<T> {
         //更新kitty的关系
         Self::new_kitty_relation(kitty_id, kitty_id_1, kitty_id_2);
 
+        //写入历史记录
+        Self::index_history(
+            kitty_id,
+            KittyAction::Bred { owner: sender.clone(), father: kitty_id_1, mother: kitty_id_2 },
+        );
+        Self::index_history(kitty_id_1, KittyAction::ParentOf { child: kitty_id, partner: kitty_id_2 });
+        Self::index_history(kitty_id_2, KittyAction::ParentOf { child: kitty_id, partner: kitty_id_1 });
+
         Ok(kitty_id)
     }
 
@@ -324,14 +714,24 @@ impl<T: Trait> Module<T> {
     //质押
     fn lock_token(sender: &T::AccountId, kitty_id: T::KittyIndex, lock_id: u32) {
         let _lock_id = Self::_decode_kitty_lock_id(lock_id);
-        T::Currency::set_lock(
-            _lock_id,
-            &sender,
-            T::KittyLockToken::get(),
-            WithdrawReasons::all(),
-        );
+        let amount = T::KittyLockToken::get();
+        T::Currency::set_lock(_lock_id, &sender, amount, WithdrawReasons::all());
         //绑定kitty-质押id
         <LockId<T>>::insert(kitty_id, lock_id);
+        <LockedAmount<T>>::insert(kitty_id, amount);
+    }
+
+    //版本2之前的kitty都按当时的KittyLockToken质押，返回写入的数量
+    fn migrate_locked_amount() -> u64 {
+        let amount = T::KittyLockToken::get();
+        let mut migrated = 0;
+        for (kitty, _) in <Kitties<T>>::iter() {
+            if !<LockedAmount<T>>::contains_key(kitty) {
+                <LockedAmount<T>>::insert(kitty, amount);
+                migrated += 1;
+            }
+        }
+        migrated
     }
 
     //转移质押
@@ -356,6 +756,17 @@ impl<T: Trait> Module<T> {
         lock_id
     }
 
+    //把历史记录写入offchain index。节点需以`--enable-offchain-indexing true`启动才会保存
+    fn index_history(kitty_id: T::KittyIndex, action: KittyAction<T::AccountId, T::KittyIndex>) {
+        let n = Self::kitty_history_len(kitty_id);
+        let record = KittyHistoryRecord {
+            block: <frame_system::Module<T>>::block_number(),
+            action,
+        };
+        sp_io::offchain_index::set(&history_key(&kitty_id, n), &record.encode());
+        <KittyHistoryLen<T>>::insert(kitty_id, n + 1);
+    }
+
     fn set_kitties_count(value: T::KittyIndex) {
         <KittiesCount<T>>::put(value);
     }
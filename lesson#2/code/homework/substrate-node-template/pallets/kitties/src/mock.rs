@@ -1,7 +1,7 @@
 use crate::{Module, Trait};
 use frame_support::{
-    impl_outer_event, impl_outer_origin, parameter_types,
-    traits::{OnFinalize, OnInitialize},
+    dispatch::DispatchResult, impl_outer_event, impl_outer_origin, parameter_types,
+    traits::{Currency, Get, OnFinalize, OnInitialize, OnUnbalanced},
     weights::Weight,
 };
 use frame_system as system;
@@ -12,6 +12,7 @@ use sp_runtime::{
     traits::{BlakeTwo256, IdentityLookup},
     Perbill,
 };
+use std::cell::RefCell;
 
 mod simple_event {
     pub use crate::Event;
@@ -84,8 +85,39 @@ impl pallet_balances::Trait for Test {
 
 type Randomness = pallet_randomness_collective_flip::Module<Test>;
 
+thread_local! {
+    static KITTY_LOCK_TOKEN: RefCell<u64> = RefCell::new(5);
+}
+
+/// The lock token, changed by `set_kitty_lock_token` like a governance parameter.
+pub struct KittyLockToken;
+
+impl Get<u64> for KittyLockToken {
+    fn get() -> u64 {
+        KITTY_LOCK_TOKEN.with(|v| *v.borrow())
+    }
+}
+
+pub fn set_kitty_lock_token(amount: u64) {
+    KITTY_LOCK_TOKEN.with(|v| *v.borrow_mut() = amount);
+}
+
 parameter_types! {
-    pub const KittyLockToken: u64 = 5;
+    pub const FreeOperations: u32 = 2;
+    pub const FreeOperationsPeriod: u64 = 10;
+    pub const BreedFee: u64 = 2;
+}
+
+//收取繁殖费的账户
+pub const FEE_POT: u64 = 99;
+
+/// Credits the breeding fees to `FEE_POT`.
+pub struct FeeToPot;
+
+impl OnUnbalanced<pallet_balances::NegativeImbalance<Test>> for FeeToPot {
+    fn on_nonzero_unbalanced(amount: pallet_balances::NegativeImbalance<Test>) {
+        Balances::resolve_creating(&FEE_POT, amount);
+    }
 }
 
 impl Trait for Test {
@@ -94,9 +126,37 @@ impl Trait for Test {
     type KittyIndex = u32;
     type Currency = pallet_balances::Module<Test>;
     type KittyLockToken = KittyLockToken;
+    type FreeOperations = FreeOperations;
+    type FreeOperationsPeriod = FreeOperationsPeriod;
+    type BreedFee = BreedFee;
+    type OnFee = FeeToPot;
 }
 
 pub type KittiesModule = Module<Test>;
+
+/// Dispatches to `KittiesModule` and checks `do_try_state` after every successful call.
+pub struct KittiesCall;
+
+impl KittiesCall {
+    fn checked(result: DispatchResult) -> DispatchResult {
+        if result.is_ok() {
+            assert_eq!(KittiesModule::do_try_state(), vec![]);
+        }
+        result
+    }
+
+    pub fn create(origin: Origin) -> DispatchResult {
+        Self::checked(KittiesModule::create(origin))
+    }
+
+    pub fn transfer(origin: Origin, to: u64, kitty_index: u32) -> DispatchResult {
+        Self::checked(KittiesModule::transfer(origin, to, kitty_index))
+    }
+
+    pub fn breed(origin: Origin, kitty_id_1: u32, kitty_id_2: u32) -> DispatchResult {
+        Self::checked(KittiesModule::breed(origin, kitty_id_1, kitty_id_2))
+    }
+}
 pub type System = frame_system::Module<Test>;
 pub type Balances = pallet_balances::Module<Test>;
 
@@ -119,6 +179,7 @@ pub fn run_to_block(n: u64) {
 
 // Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
+    set_kitty_lock_token(5);
     let mut t = system::GenesisConfig::default()
         .build_storage::<Test>()
         .unwrap();
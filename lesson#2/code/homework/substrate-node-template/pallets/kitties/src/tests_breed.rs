@@ -1,6 +1,7 @@
 use super::*;
 use crate::{mock::*, Error};
 use frame_support::{assert_noop, assert_ok};
+use sp_core::offchain::OffchainStorage;
 
 //繁殖kitty成功
 #[test]
@@ -8,16 +9,16 @@ fn breed_kitties_work() {
     new_test_ext().execute_with(|| {
         run_to_block(10);
         Balances::deposit_creating(&1, 100);
-        assert_ok!(KittiesModule::create(Origin::signed(1)));
-        assert_ok!(KittiesModule::create(Origin::signed(1)));
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
         assert_eq!(KittiesModule::lock_id(0), 0); // test lock_id
         assert_eq!(KittiesModule::lock_id(1), 1); // test lock_id
         assert_eq!(KittiesModule::lock_index(), 2); // test lock_index
 
-        assert_ok!(KittiesModule::breed(Origin::signed(1), 0, 1));
+        assert_ok!(KittiesCall::breed(Origin::signed(1), 0, 1));
         assert_eq!(KittiesModule::kitties_count(), 3); //test count
         assert_eq!(KittiesModule::kitty_owners(2), Some(1)); //test owner
-        assert_eq!(Balances::usable_balance(&1), 95); //test lock
+        assert_eq!(Balances::usable_balance(&1), 93); //test lock，已扣除繁殖费
 
         assert_eq!(
             last_event(),
@@ -32,11 +33,11 @@ fn breed_kitties_when_lock_indx_overflow() {
     new_test_ext().execute_with(|| {
         run_to_block(10);
         Balances::deposit_creating(&1, 100);
-        assert_ok!(KittiesModule::create(Origin::signed(1)));
-        assert_ok!(KittiesModule::create(Origin::signed(1)));
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
         KittiesModule::set_lock_index(9999999);
         assert_noop!(
-            KittiesModule::breed(Origin::signed(1), 0, 1),
+            KittiesCall::breed(Origin::signed(1), 0, 1),
             Error::<Test>::LockIndexOverflow
         );
     })
@@ -48,8 +49,8 @@ fn breed_kitties_when_balance_not_enough() {
     new_test_ext().execute_with(|| {
         run_to_block(10);
         Balances::deposit_creating(&1, 100);
-        assert_ok!(KittiesModule::create(Origin::signed(1)));
-        assert_ok!(KittiesModule::create(Origin::signed(1)));
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
         assert_ok!(Balances::transfer(Origin::signed(1), 2, 1));
         // assert_eq!(KittiesModule::lock_id(0), 0); // test lock_id
         // assert_eq!(KittiesModule::lock_id(1), 1); // test lock_id
@@ -57,10 +58,10 @@ fn breed_kitties_when_balance_not_enough() {
         // assert_eq!(KittiesModule::next_lock_index(), Ok(2));
 
         assert_noop!(
-            KittiesModule::breed(Origin::signed(2), 0, 1),
+            KittiesCall::breed(Origin::signed(2), 0, 1),
             Error::<Test>::FreeNotEnough
         );
-        // assert_ok!(KittiesModule::breed(Origin::signed(2), 0, 1));
+        // assert_ok!(KittiesCall::breed(Origin::signed(2), 0, 1));
     })
 }
 
@@ -70,10 +71,10 @@ fn breed_kitties_when_invaild_kitty_id() {
     new_test_ext().execute_with(|| {
         run_to_block(10);
         Balances::deposit_creating(&1, 100);
-        assert_ok!(KittiesModule::create(Origin::signed(1)));
-        assert_ok!(KittiesModule::create(Origin::signed(1)));
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
         assert_noop!(
-            KittiesModule::breed(Origin::signed(1), 0, 2),
+            KittiesCall::breed(Origin::signed(1), 0, 2),
             Error::<Test>::InvaildKittyId
         );
     })
@@ -85,11 +86,98 @@ fn breed_kitties_when_require_different_parent() {
     new_test_ext().execute_with(|| {
         run_to_block(10);
         Balances::deposit_creating(&1, 100);
-        assert_ok!(KittiesModule::create(Origin::signed(1)));
-        assert_ok!(KittiesModule::create(Origin::signed(1)));
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
         assert_noop!(
-            KittiesModule::breed(Origin::signed(1), 0, 0),
+            KittiesCall::breed(Origin::signed(1), 0, 0),
             Error::<Test>::RequireDifferentParent
         );
     })
 }
+
+//创建、转移、繁殖都会写入历史记录
+#[test]
+fn history_is_indexed() {
+    let mut ext = new_test_ext();
+    ext.execute_with(|| {
+        run_to_block(10);
+        Balances::deposit_creating(&1, 100);
+        Balances::deposit_creating(&2, 100);
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
+        assert_eq!(KittiesModule::kitty_history_len(0), 1);
+        assert_eq!(KittiesModule::kitty_history_len(1), 1);
+
+        assert_ok!(KittiesCall::breed(Origin::signed(1), 0, 1));
+        assert_eq!(KittiesModule::kitty_history_len(0), 2); //ParentOf
+        assert_eq!(KittiesModule::kitty_history_len(1), 2); //ParentOf
+        assert_eq!(KittiesModule::kitty_history_len(2), 1); //Bred
+
+        assert_ok!(KittiesCall::transfer(Origin::signed(1), 2, 2));
+        assert_eq!(KittiesModule::kitty_history_len(2), 2);
+
+        //失败的调用不写历史记录
+        assert_noop!(
+            KittiesCall::transfer(Origin::signed(1), 2, 9),
+            Error::<Test>::InvaildKittyId
+        );
+        assert_eq!(KittiesModule::kitty_history_len(9), 0);
+    });
+
+    //读回offchain index里的繁殖记录
+    ext.persist_offchain_overlay();
+    let raw = ext.offchain_db()
+        .get(sp_core::offchain::STORAGE_PREFIX, &history_key(&2u32, 0))
+        .expect("the first record of kitty 2 is indexed");
+    assert_eq!(
+        KittyHistoryRecord::<u64, u32, u64>::decode(&mut &raw[..]).unwrap(),
+        KittyHistoryRecord {
+            block: 10,
+            action: KittyAction::Bred { owner: 1, father: 0, mother: 1 },
+        }
+    );
+}
+
+#[test]
+fn history_keys_are_distinct() {
+    assert_ne!(history_key(&0u32, 1), history_key(&1u32, 0));
+    assert!(history_key(&0u32, 0).starts_with(&HISTORY_INDEX_PREFIX.encode()));
+}
+
+//kitty信息包含父母、孩子和解码后的样式
+#[test]
+fn kitty_info_works() {
+    new_test_ext().execute_with(|| {
+        run_to_block(10);
+        Balances::deposit_creating(&1, 100);
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
+        assert_ok!(KittiesCall::breed(Origin::signed(1), 0, 1));
+
+        let child = KittiesModule::kitty_info(2).unwrap();
+        assert_eq!(child.owner, 1);
+        assert_eq!(child.parents, Some((0, 1)));
+        assert_eq!(child.genome, Kitty(child.dna).genome());
+        assert_eq!(Some(child.genome.clone()), KittiesModule::kitty_genome(2));
+
+        let father = KittiesModule::kitty_info(0).unwrap();
+        assert_eq!(father.parents, None);
+        assert_eq!(father.children, vec![2]);
+        assert_eq!(KittiesModule::kitty_info(3), None);
+    })
+}
+
+#[test]
+fn genome_uses_variant_counts() {
+    let genome = Kitty([255; 16]).genome();
+    assert_eq!(
+        genome,
+        KittyGenome {
+            body: 255 % BODY_VARIANTS,
+            eyes: 255 % EYES_VARIANTS,
+            accessory: 255 % ACCESSORY_VARIANTS,
+            fur: 255 % FUR_VARIANTS,
+            mouth: 255 % MOUTH_VARIANTS,
+        }
+    );
+}
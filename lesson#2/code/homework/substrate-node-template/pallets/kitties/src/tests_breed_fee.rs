@@ -0,0 +1,72 @@
+use super::*;
+use crate::{mock::*, Error};
+use frame_support::{assert_noop, assert_ok};
+
+//繁殖费从owner扣除并交给OnFee
+#[test]
+fn breed_fee_goes_to_on_fee() {
+    new_test_ext().execute_with(|| {
+        run_to_block(10);
+        Balances::deposit_creating(&1, 100);
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
+        let issuance = Balances::total_issuance();
+
+        assert_ok!(KittiesCall::breed(Origin::signed(1), 0, 1));
+        assert_eq!(Balances::free_balance(&1), 98);
+        assert_eq!(Balances::free_balance(&FEE_POT), 2);
+        assert_eq!(Balances::total_issuance(), issuance);
+    })
+}
+
+//创建和转移不收费
+#[test]
+fn create_and_transfer_are_free() {
+    new_test_ext().execute_with(|| {
+        run_to_block(10);
+        Balances::deposit_creating(&1, 100);
+        Balances::deposit_creating(&2, 100);
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
+        assert_ok!(KittiesCall::transfer(Origin::signed(1), 2, 0));
+        assert_eq!(Balances::free_balance(&1), 100);
+        assert_eq!(Balances::free_balance(&2), 100);
+        assert_eq!(Balances::free_balance(&FEE_POT), 0);
+    })
+}
+
+//付不起繁殖费时不扣费
+#[test]
+fn breed_fee_not_affordable() {
+    new_test_ext().execute_with(|| {
+        run_to_block(10);
+        Balances::deposit_creating(&1, 6);
+        Balances::deposit_creating(&2, 100);
+        assert_ok!(KittiesCall::create(Origin::signed(2)));
+        assert_ok!(KittiesCall::create(Origin::signed(2)));
+        assert_ok!(KittiesCall::transfer(Origin::signed(2), 1, 0));
+        assert_ok!(KittiesCall::transfer(Origin::signed(2), 1, 1));
+
+        //扣费后余额低于已有的质押
+        assert_noop!(
+            KittiesCall::breed(Origin::signed(1), 0, 1),
+            Error::<Test>::FreeNotEnough
+        );
+        assert_eq!(Balances::free_balance(&FEE_POT), 0);
+    })
+}
+
+//繁殖失败时退回繁殖费
+#[test]
+fn failed_breed_refunds_fee() {
+    new_test_ext().execute_with(|| {
+        run_to_block(10);
+        Balances::deposit_creating(&1, 100);
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
+        assert_noop!(
+            KittiesCall::breed(Origin::signed(1), 0, 1),
+            Error::<Test>::InvaildKittyId
+        );
+        assert_eq!(Balances::free_balance(&1), 100);
+        assert_eq!(Balances::free_balance(&FEE_POT), 0);
+    })
+}
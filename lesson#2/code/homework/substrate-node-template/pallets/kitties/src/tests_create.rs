@@ -10,8 +10,8 @@ fn owned_kitties_can_append_values() {
         run_to_block(10);
         // assert_ok!(Balances::transfer(Origin::signed(10), 20, 1));
         Balances::deposit_creating(&1, 100);
-        assert_ok!(KittiesModule::create(Origin::signed(1)));
-        assert_ok!(KittiesModule::create(Origin::signed(1)));
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
 
         assert_eq!(KittiesModule::lock_id(0), 0); // test lock_id
         assert_eq!(KittiesModule::lock_index(), 2); // test lock_index
@@ -41,7 +41,7 @@ fn create_kitties_when_index_max() {
         run_to_block(10);
         KittiesModule::set_kitties_count(<mock::Test as Trait>::KittyIndex::max_value());
         assert_noop!(
-            KittiesModule::create(Origin::signed(1)),
+            KittiesCall::create(Origin::signed(1)),
             Error::<Test>::KittiesCountOverflow
         );
     })
@@ -54,7 +54,7 @@ fn create_kitties_when_lock_max() {
         run_to_block(10);
         KittiesModule::set_lock_index(9999999);
         assert_noop!(
-            KittiesModule::create(Origin::signed(1)),
+            KittiesCall::create(Origin::signed(1)),
             Error::<Test>::LockIndexOverflow
         );
     })
@@ -67,8 +67,102 @@ fn create_kitties_when_balance_not_enough() {
         run_to_block(10);
         Balances::deposit_creating(&1, 1);
         assert_noop!(
-            KittiesModule::create(Origin::signed(1)),
+            KittiesCall::create(Origin::signed(1)),
             Error::<Test>::FreeNotEnough
         );
     })
 }
+
+//创世kitty
+#[test]
+fn genesis_kitties_are_created_and_locked() {
+    let mut t = frame_system::GenesisConfig::default()
+        .build_storage::<Test>()
+        .unwrap();
+    pallet_balances::GenesisConfig::<Test> {
+        balances: vec![(1, 100), (2, 100)],
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+    GenesisConfig::<Test> {
+        kitties: vec![(1, [1; 16]), (2, [2; 16]), (1, [3; 16])],
+        relations: vec![(2, 0, 1)],
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+
+    sp_io::TestExternalities::from(t).execute_with(|| {
+        assert_eq!(KittiesModule::kitties_count(), 3);
+        assert_eq!(KittiesModule::kitties(1).map(|k| k.0), Some([2; 16]));
+        assert_eq!(KittiesModule::kitty_owners(2), Some(1));
+        assert_eq!(KittiesModule::user_kitties(1), vec![0, 2]);
+        assert_eq!(KittiesModule::lock_id(2), 2);
+        assert_eq!(KittiesModule::lock_index(), 3);
+        assert_eq!(Balances::usable_balance(&1), 95);
+        assert_eq!(KittiesModule::kitty_info(2).unwrap().parents, Some((0, 1)));
+        assert_eq!(KittiesModule::kitty_info(0).unwrap().children, vec![2]);
+        assert_eq!(KittiesModule::kitty_info(1).unwrap().parents, None);
+
+        //创世之后继续正常创建
+        assert_ok!(KittiesCall::create(Origin::signed(2)));
+        assert_eq!(KittiesModule::kitty_owners(3), Some(2));
+        assert_eq!(KittiesModule::lock_id(3), 3);
+    })
+}
+
+//创世kitty的owner余额不足
+#[test]
+#[should_panic(expected = "genesis kitty owner can't afford the lock")]
+fn genesis_kitty_owner_must_afford_the_lock() {
+    let mut t = frame_system::GenesisConfig::default()
+        .build_storage::<Test>()
+        .unwrap();
+    GenesisConfig::<Test> {
+        kitties: vec![(1, [1; 16])],
+        relations: vec![],
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+}
+
+//创世关系引用了不存在的kitty
+#[test]
+#[should_panic(expected = "genesis relation of an unknown kitty")]
+fn genesis_relations_must_reference_genesis_kitties() {
+    let mut t = frame_system::GenesisConfig::default()
+        .build_storage::<Test>()
+        .unwrap();
+    pallet_balances::GenesisConfig::<Test> {
+        balances: vec![(1, 100)],
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+    GenesisConfig::<Test> {
+        kitties: vec![(1, [1; 16]), (1, [2; 16])],
+        relations: vec![(1, 0, 2)],
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+}
+
+//KittyLockToken修改后只影响之后的质押
+#[test]
+fn lock_token_change_applies_to_new_locks() {
+    new_test_ext().execute_with(|| {
+        run_to_block(10);
+        Balances::deposit_creating(&1, 100);
+        Balances::deposit_creating(&2, 100);
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
+
+        set_kitty_lock_token(8);
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
+        assert_eq!(KittiesModule::locked_amount(0), 5);
+        assert_eq!(KittiesModule::locked_amount(1), 8);
+        assert_eq!(Balances::usable_balance(&1), 92);
+
+        //转移时按新的KittyLockToken质押
+        assert_ok!(KittiesCall::transfer(Origin::signed(1), 2, 0));
+        assert_eq!(KittiesModule::locked_amount(0), 8);
+        assert_eq!(Balances::usable_balance(&2), 92);
+    })
+}
@@ -0,0 +1,28 @@
+use super::*;
+use crate::mock::*;
+
+//每个周期有FreeOperations次免费操作
+#[test]
+fn free_operations_are_limited_per_period() {
+    new_test_ext().execute_with(|| {
+        run_to_block(1);
+        assert_eq!(KittiesModule::free_operations_left(&1), 2);
+        assert!(KittiesModule::use_free_operation(&1));
+        assert!(KittiesModule::use_free_operation(&1));
+        assert!(!KittiesModule::use_free_operation(&1));
+        assert_eq!(KittiesModule::free_operations_left(&1), 0);
+        //其他账户不受影响
+        assert_eq!(KittiesModule::free_operations_left(&2), 2);
+
+        //同一周期内不恢复
+        run_to_block(9);
+        assert!(!KittiesModule::use_free_operation(&1));
+
+        //新周期重新计数
+        run_to_block(10);
+        assert_eq!(KittiesModule::free_operations_left(&1), 2);
+        assert!(KittiesModule::use_free_operation(&1));
+        assert_eq!(KittiesModule::free_operations_used(&1), (1, 1));
+        assert_eq!(KittiesModule::free_operations_left(&1), 1);
+    })
+}
@@ -0,0 +1,128 @@
+//! 随机的create/transfer/breed序列，每一步之后检查存储约束和质押。
+//! 失败的用例会被缩小并记录到proptest-regressions/tests_proptest.txt，之后的运行会先重放其中的种子；
+//! 用`PROPTEST_CASES`调整用例数量。
+
+use super::*;
+use crate::mock::*;
+use frame_support::{assert_ok, storage_root};
+use proptest::prelude::*;
+
+const ACCOUNTS: u64 = 4;
+const MAX_KITTY_ID: u32 = 12;
+
+#[derive(Clone, Debug)]
+enum Op {
+    Create { who: u64 },
+    Transfer { who: u64, to: u64, kitty: u32 },
+    Breed { who: u64, father: u32, mother: u32 },
+    //转出余额，让余额可能低于质押
+    Spend { who: u64, amount: u64 },
+    NextBlock,
+}
+
+fn account() -> impl Strategy<Value = u64> {
+    1..=ACCOUNTS
+}
+
+fn kitty() -> impl Strategy<Value = u32> {
+    0..MAX_KITTY_ID
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        3 => account().prop_map(|who| Op::Create { who }),
+        3 => (account(), account(), kitty()).prop_map(|(who, to, kitty)| Op::Transfer { who, to, kitty }),
+        3 => (account(), kitty(), kitty()).prop_map(|(who, father, mother)| Op::Breed { who, father, mother }),
+        1 => (account(), 1..20u64).prop_map(|(who, amount)| Op::Spend { who, amount }),
+        1 => Just(Op::NextBlock),
+    ]
+}
+
+//账户1到4的余额从多到少，4一开始就付不起质押
+fn endow() {
+    for (who, balance) in &[(1, 100), (2, 40), (3, 10), (4, 3)] {
+        Balances::deposit_creating(who, *balance);
+    }
+}
+
+//质押：每只kitty在owner上有一个KittyLockToken的锁，没有别的锁
+fn check_locks(owners: &[u64]) {
+    for who in 1..=ACCOUNTS {
+        let owned = owners.iter().filter(|owner| **owner == who).count();
+        let locks = Balances::locks(&who);
+        assert_eq!(locks.len(), owned, "locks of {}", who);
+        assert!(locks.iter().all(|lock| lock.amount == KittyLockToken::get()));
+    }
+}
+
+fn run(ops: Vec<Op>) {
+    new_test_ext().execute_with(|| {
+        run_to_block(1);
+        endow();
+        //每只kitty的owner，下标即kitty id
+        let mut owners: Vec<u64> = Vec::new();
+
+        for op in ops {
+            let root = storage_root();
+            let result = match op {
+                Op::Create { who } => KittiesCall::create(Origin::signed(who)).map(|_| {
+                    owners.push(who);
+                }),
+                Op::Transfer { who, to, kitty } => {
+                    KittiesCall::transfer(Origin::signed(who), to, kitty).map(|_| {
+                        owners[kitty as usize] = to;
+                    })
+                }
+                Op::Breed { who, father, mother } => {
+                    KittiesCall::breed(Origin::signed(who), father, mother).map(|_| {
+                        owners.push(who);
+                    })
+                }
+                Op::Spend { who, amount } => {
+                    let _ = Balances::transfer(Origin::signed(who), ACCOUNTS + 1, amount);
+                    Ok(())
+                }
+                Op::NextBlock => {
+                    run_to_block(System::block_number() + 1);
+                    Ok(())
+                }
+            };
+            //失败的调用不能修改存储
+            if result.is_err() {
+                assert_eq!(storage_root(), root, "{:?} failed but changed the storage", op);
+            }
+
+            assert_eq!(KittiesModule::do_try_state(), vec![]);
+            assert_eq!(KittiesModule::kitties_count() as usize, owners.len());
+            for (kitty, owner) in owners.iter().enumerate() {
+                assert_eq!(KittiesModule::kitty_owners(kitty as u32), Some(*owner));
+            }
+            check_locks(&owners);
+        }
+    })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn random_calls_keep_storage_consistent(ops in proptest::collection::vec(op(), 1..60)) {
+        run(ops);
+    }
+}
+
+//固定的序列，保证各种调用都被执行到
+#[test]
+fn fixed_sequence_keeps_storage_consistent() {
+    run(vec![
+        Op::Create { who: 1 },
+        Op::Create { who: 2 },
+        Op::Breed { who: 1, father: 0, mother: 1 },
+        Op::Transfer { who: 1, to: 3, kitty: 2 },
+        Op::Create { who: 4 },
+        Op::Spend { who: 3, amount: 8 },
+        Op::NextBlock,
+        Op::Breed { who: 2, father: 1, mother: 2 },
+        Op::Transfer { who: 3, to: 2, kitty: 2 },
+    ]);
+}
@@ -10,8 +10,8 @@ fn transfer_kitties_work() {
         run_to_block(10);
         Balances::deposit_creating(&1, 100);
         Balances::deposit_creating(&2, 100);
-        assert_ok!(KittiesModule::create(Origin::signed(1)));
-        assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 0));
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
+        assert_ok!(KittiesCall::transfer(Origin::signed(1), 2, 0));
 
         assert_eq!(KittiesModule::kitties_count(), 1); //test count
         assert_eq!(KittiesModule::kitty_owners(0), Some(2)); //test owner
@@ -31,9 +31,9 @@ fn transfer_kitties_when_balance_not_enough() {
     new_test_ext().execute_with(|| {
         run_to_block(10);
         Balances::deposit_creating(&1, 100);
-        assert_ok!(KittiesModule::create(Origin::signed(1)));
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
         assert_noop!(
-            KittiesModule::transfer(Origin::signed(1), 2, 0),
+            KittiesCall::transfer(Origin::signed(1), 2, 0),
             Error::<Test>::FreeNotEnough
         );
     })
@@ -46,9 +46,9 @@ fn transfer_kitties_when_invaild_kitty_id() {
         run_to_block(10);
         Balances::deposit_creating(&1, 100);
         Balances::deposit_creating(&2, 100);
-        assert_ok!(KittiesModule::create(Origin::signed(1)));
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
         assert_noop!(
-            KittiesModule::transfer(Origin::signed(1), 2, 1),
+            KittiesCall::transfer(Origin::signed(1), 2, 1),
             Error::<Test>::InvaildKittyId
         );
     })
@@ -62,9 +62,9 @@ fn transfer_kitties_when_not_kitty_owner() {
         Balances::deposit_creating(&1, 100);
         Balances::deposit_creating(&2, 100);
         Balances::deposit_creating(&3, 100);
-        assert_ok!(KittiesModule::create(Origin::signed(1)));
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
         assert_noop!(
-            KittiesModule::transfer(Origin::signed(2), 3, 0),
+            KittiesCall::transfer(Origin::signed(2), 3, 0),
             Error::<Test>::NotKittyOwner
         );
     })
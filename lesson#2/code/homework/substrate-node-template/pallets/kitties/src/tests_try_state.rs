@@ -0,0 +1,97 @@
+use super::*;
+use crate::mock::*;
+use frame_support::{assert_ok, traits::LockableCurrency};
+
+//1有kitty 0和1，繁殖出kitty 2
+fn setup() {
+    Balances::deposit_creating(&1, 100);
+    assert_ok!(KittiesCall::create(Origin::signed(1)));
+    assert_ok!(KittiesCall::create(Origin::signed(1)));
+    assert_ok!(KittiesCall::breed(Origin::signed(1), 0, 1));
+}
+
+//KittiesCount与kitty数量不一致
+#[test]
+fn try_state_reports_count_mismatch() {
+    new_test_ext().execute_with(|| {
+        setup();
+        KittiesModule::set_kitties_count(2);
+        assert_eq!(
+            KittiesModule::do_try_state(),
+            vec![
+                Violation::BeyondCount { kitty: 2 },
+                Violation::CountMismatch { count: 2, kitties: 3 },
+            ]
+        );
+    })
+}
+
+//其他存储引用了不存在的kitty
+#[test]
+fn try_state_reports_unknown_kitties() {
+    new_test_ext().execute_with(|| {
+        setup();
+        <Kitties<Test>>::remove(2);
+        let violations = KittiesModule::do_try_state();
+        assert!(violations.contains(&Violation::CountMismatch { count: 3, kitties: 2 }));
+        assert!(violations.contains(&Violation::UnknownKitty { kitty: 2 }));
+        assert!(violations.contains(&Violation::ChildMismatch { kitty: 0, child: 2 }));
+        assert!(violations.contains(&Violation::ChildMismatch { kitty: 1, child: 2 }));
+    })
+}
+
+//UserKitties中重复的kitty
+#[test]
+fn try_state_reports_duplicate_user_kitties() {
+    new_test_ext().execute_with(|| {
+        setup();
+        <UserKitties<Test>>::insert(1, vec![0, 1, 2, 1]);
+        assert_eq!(
+            KittiesModule::do_try_state(),
+            vec![Violation::DuplicateInUserKitties { kitty: 1, account: 1 }]
+        );
+    })
+}
+
+//父母与孩子的关系不一致
+#[test]
+fn try_state_reports_relation_mismatches() {
+    new_test_ext().execute_with(|| {
+        setup();
+        <KittyRelations<Test>>::mutate(0, |relation| relation.children.clear());
+        assert_eq!(
+            KittiesModule::do_try_state(),
+            vec![Violation::ParentMismatch { kitty: 2, parent: 0 }]
+        );
+    })
+}
+
+//质押与owner不一致
+#[test]
+fn try_state_reports_lock_mismatches() {
+    new_test_ext().execute_with(|| {
+        setup();
+        <LockId<Test>>::insert(1, 0);
+        let violations = KittiesModule::do_try_state();
+        assert!(violations.contains(&Violation::SharedLock { kitty: 1, lock_id: 0 })
+            || violations.contains(&Violation::SharedLock { kitty: 0, lock_id: 0 }));
+
+        <LockId<Test>>::insert(1, 1);
+        <LockId<Test>>::insert(2, 7);
+        assert_eq!(
+            KittiesModule::do_try_state(),
+            vec![Violation::LockBeyondIndex { kitty: 2, lock_id: 7 }]
+        );
+
+        <LockId<Test>>::insert(2, 2);
+        for lock_id in 0..3 {
+            Balances::remove_lock(KittiesModule::_decode_kitty_lock_id(lock_id), &1);
+        }
+        let violations = KittiesModule::do_try_state();
+        assert_eq!(violations.len(), 3);
+        assert!(violations.contains(&Violation::OwnerNotLocked { kitty: 0, owner: 1 }));
+
+        <LockId<Test>>::remove(0);
+        assert!(KittiesModule::do_try_state().contains(&Violation::MissingLock { kitty: 0 }));
+    })
+}
@@ -0,0 +1,66 @@
+use super::*;
+use crate::mock::*;
+use frame_support::{assert_ok, traits::OnRuntimeUpgrade};
+
+//升级记录存储版本
+#[test]
+fn runtime_upgrade_sets_storage_version() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(KittiesModule::storage_version(), 0);
+        KittiesModule::on_runtime_upgrade();
+        assert_eq!(KittiesModule::storage_version(), STORAGE_VERSION);
+        KittiesModule::on_runtime_upgrade();
+        assert_eq!(KittiesModule::storage_version(), STORAGE_VERSION);
+    })
+}
+
+//正常操作后没有违反约束
+#[test]
+fn post_upgrade_passes_after_dispatches() {
+    new_test_ext().execute_with(|| {
+        Balances::deposit_creating(&1, 100);
+        Balances::deposit_creating(&2, 100);
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
+        assert_ok!(KittiesCall::breed(Origin::signed(1), 0, 1));
+        assert_ok!(KittiesCall::transfer(Origin::signed(1), 2, 2));
+        assert_eq!(KittiesModule::post_upgrade(), vec![]);
+    })
+}
+
+//owner与UserKitties不一致
+#[test]
+fn post_upgrade_reports_owner_mismatches() {
+    new_test_ext().execute_with(|| {
+        Balances::deposit_creating(&1, 100);
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
+        <KittyOwners<Test>>::insert(0, 2);
+
+        let violations = KittiesModule::post_upgrade();
+        assert_eq!(violations.len(), 3);
+        assert!(violations.contains(&Violation::NotInUserKitties { kitty: 0, owner: 2 }));
+        assert!(violations.contains(&Violation::WrongOwner { kitty: 0, account: 1, owner: Some(2) }));
+        //2没有为kitty质押
+        assert!(violations.contains(&Violation::OwnerNotLocked { kitty: 0, owner: 2 }));
+    })
+}
+
+//版本1没有LockedAmount，升级时按当前的KittyLockToken补上
+#[test]
+fn runtime_upgrade_migrates_locked_amount() {
+    new_test_ext().execute_with(|| {
+        Balances::deposit_creating(&1, 100);
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
+        assert_ok!(KittiesCall::create(Origin::signed(1)));
+        StorageVersion::put(1);
+        <LockedAmount<Test>>::remove(0);
+        <LockedAmount<Test>>::remove(1);
+        assert!(KittiesModule::post_upgrade().contains(&Violation::MissingLock { kitty: 0 }));
+
+        KittiesModule::on_runtime_upgrade();
+        assert_eq!(KittiesModule::storage_version(), STORAGE_VERSION);
+        assert_eq!(KittiesModule::locked_amount(0), 5);
+        assert_eq!(KittiesModule::locked_amount(1), 5);
+        assert_eq!(KittiesModule::post_upgrade(), vec![]);
+    })
+}
@@ -0,0 +1,147 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Runtime parameters that governance can change without a runtime upgrade.
+//!
+//! The runtime reads them through `Get` implementations backed by `Module::parameters`.
+//! Until the first update, `Trait::Defaults` is used.
+//!
+//! Runtime upgrades go through `set_code`, so governance needs no root key.
+
+use codec::{Decode, Encode};
+use frame_support::{
+    decl_error, decl_event, decl_module, decl_storage, ensure,
+    traits::{EnsureOrigin, Get},
+    weights::DispatchClass,
+    Parameter,
+};
+use frame_system::RawOrigin;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::{
+    traits::{AtLeast32BitUnsigned, Zero},
+    Permill, RuntimeDebug,
+};
+use sp_std::vec::Vec;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// All runtime parameters.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct RuntimeParameters<Balance> {
+    /// Minimum balance of an account, see `pallet_balances::Trait::ExistentialDeposit`. It can
+    /// only be lowered, accounts below a raised deposit would never be reaped.
+    pub existential_deposit: Balance,
+    /// Locked for each kitty, applies to kitties created or transferred afterwards.
+    pub kitty_lock_token: Balance,
+    /// Paid to the treasury for each bred kitty.
+    pub kitty_breed_fee: Balance,
+    /// Kitty operations each account may do without transaction fees per period.
+    pub free_kitty_operations: u32,
+    /// Discount on the transaction fee of kitty operations once the free ones are used up.
+    pub kitty_fee_discount: Permill,
+    /// Share of transaction fees and tips that goes to the treasury.
+    pub treasury_fee_share: Permill,
+}
+
+/// A change of a single parameter.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum ParameterUpdate<Balance> {
+    ExistentialDeposit(Balance),
+    KittyLockToken(Balance),
+    KittyBreedFee(Balance),
+    FreeKittyOperations(u32),
+    KittyFeeDiscount(Permill),
+    TreasuryFeeShare(Permill),
+}
+
+impl<Balance> RuntimeParameters<Balance> {
+    //应用一个修改
+    pub fn apply(&mut self, update: ParameterUpdate<Balance>) {
+        match update {
+            ParameterUpdate::ExistentialDeposit(value) => self.existential_deposit = value,
+            ParameterUpdate::KittyLockToken(value) => self.kitty_lock_token = value,
+            ParameterUpdate::KittyBreedFee(value) => self.kitty_breed_fee = value,
+            ParameterUpdate::FreeKittyOperations(value) => self.free_kitty_operations = value,
+            ParameterUpdate::KittyFeeDiscount(value) => self.kitty_fee_discount = value,
+            ParameterUpdate::TreasuryFeeShare(value) => self.treasury_fee_share = value,
+        }
+    }
+}
+
+pub trait Trait: frame_system::Trait {
+    type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+    type Balance: Parameter + AtLeast32BitUnsigned + Default + Copy;
+    /// Who may change the parameters, e.g. a council motion.
+    type UpdateOrigin: EnsureOrigin<Self::Origin>;
+    /// Who may upgrade the runtime, e.g. a council motion.
+    type UpgradeOrigin: EnsureOrigin<Self::Origin>;
+    /// The parameters until they are changed for the first time.
+    type Defaults: Get<RuntimeParameters<Self::Balance>>;
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as Parameters {
+        //修改过的参数，None时使用Defaults
+        pub Current get(fn current): Option<RuntimeParameters<T::Balance>>;
+    }
+}
+
+decl_event!(
+    pub enum Event<T>
+    where
+        Balance = <T as Trait>::Balance,
+    {
+        Updated(ParameterUpdate<Balance>), //参数已修改
+    }
+);
+
+decl_error! {
+    pub enum Error for Module<T: Trait> {
+        //ExistentialDeposit不能为0，否则账户永远不会被清理
+        ZeroExistentialDeposit,
+        //ExistentialDeposit只能降低，已有账户的余额可能低于更高的值
+        ExistentialDepositRaised,
+    }
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+
+        type Error = Error<T>;
+
+        fn deposit_event() = default;
+
+        #[weight = T::DbWeight::get().reads_writes(1, 1)]
+        pub fn update(origin, update: ParameterUpdate<T::Balance>) {
+            T::UpdateOrigin::ensure_origin(origin)?;
+            let mut parameters = Self::parameters();
+            if let ParameterUpdate::ExistentialDeposit(value) = &update {
+                ensure!(!value.is_zero(), Error::<T>::ZeroExistentialDeposit);
+                ensure!(*value <= parameters.existential_deposit, Error::<T>::ExistentialDepositRaised);
+            }
+            parameters.apply(update.clone());
+            Current::<T>::put(parameters);
+            Self::deposit_event(RawEvent::Updated(update));
+        }
+
+        //升级runtime，以root身份调用system.set_code
+        #[weight = (T::MaximumBlockWeight::get(), DispatchClass::Operational)]
+        pub fn set_code(origin, code: Vec<u8>) {
+            T::UpgradeOrigin::ensure_origin(origin)?;
+            frame_system::Module::<T>::set_code(RawOrigin::Root.into(), code)?;
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    //当前生效的参数
+    pub fn parameters() -> RuntimeParameters<T::Balance> {
+        Self::current().unwrap_or_else(T::Defaults::get)
+    }
+}
@@ -0,0 +1,97 @@
+use crate::{Module, RuntimeParameters, Trait};
+use frame_support::{impl_outer_event, impl_outer_origin, parameter_types, weights::Weight};
+use frame_system as system;
+use frame_system::EnsureRoot;
+use sp_core::H256;
+use sp_runtime::{
+    testing::Header,
+    traits::{BlakeTwo256, IdentityLookup},
+    Perbill, Permill,
+};
+
+mod parameters {
+    pub use crate::Event;
+}
+
+impl_outer_origin! {
+    pub enum Origin for Test {}
+}
+
+impl_outer_event! {
+    pub enum TestEvent for Test {
+        parameters<T>,
+        frame_system<T>,
+    }
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+    pub const MaximumBlockWeight: Weight = 1024;
+    pub const MaximumBlockLength: u32 = 2 * 1024;
+    pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+}
+
+impl system::Trait for Test {
+    type BaseCallFilter = ();
+    type Origin = Origin;
+    type Call = ();
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = u64;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = TestEvent;
+    type BlockHashCount = BlockHashCount;
+    type MaximumBlockWeight = MaximumBlockWeight;
+    type DbWeight = ();
+    type BlockExecutionWeight = ();
+    type ExtrinsicBaseWeight = ();
+    type MaximumExtrinsicWeight = MaximumBlockWeight;
+    type MaximumBlockLength = MaximumBlockLength;
+    type AvailableBlockRatio = AvailableBlockRatio;
+    type Version = ();
+    type PalletInfo = ();
+    type AccountData = ();
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+}
+
+parameter_types! {
+    pub Defaults: RuntimeParameters<u64> = RuntimeParameters {
+        existential_deposit: 10,
+        kitty_lock_token: 5,
+        kitty_breed_fee: 2,
+        free_kitty_operations: 10,
+        kitty_fee_discount: Permill::from_percent(50),
+        treasury_fee_share: Permill::from_percent(20),
+    };
+}
+
+impl Trait for Test {
+    type Event = TestEvent;
+    type Balance = u64;
+    type UpdateOrigin = EnsureRoot<u64>;
+    type UpgradeOrigin = EnsureRoot<u64>;
+    type Defaults = Defaults;
+}
+
+pub type Parameters = Module<Test>;
+pub type System = frame_system::Module<Test>;
+
+pub fn last_event() -> TestEvent {
+    System::events().pop().expect("Event expected").event
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let t = system::GenesisConfig::default()
+        .build_storage::<Test>()
+        .unwrap();
+    let mut ext: sp_io::TestExternalities = t.into();
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
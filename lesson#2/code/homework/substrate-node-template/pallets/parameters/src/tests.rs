@@ -0,0 +1,98 @@
+use super::*;
+use crate::mock::*;
+use frame_support::{assert_noop, assert_ok};
+use sp_runtime::DispatchError;
+
+//未修改时使用默认值
+#[test]
+fn defaults_are_used_until_updated() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(Parameters::current(), None);
+        assert_eq!(Parameters::parameters(), Defaults::get());
+    })
+}
+
+//修改一个参数不影响其他参数
+#[test]
+fn update_changes_one_parameter() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Parameters::update(
+            Origin::root(),
+            ParameterUpdate::KittyLockToken(10)
+        ));
+        let mut expected = Defaults::get();
+        expected.kitty_lock_token = 10;
+        assert_eq!(Parameters::parameters(), expected);
+        assert_eq!(
+            last_event(),
+            TestEvent::parameters(RawEvent::Updated(ParameterUpdate::KittyLockToken(10)))
+        );
+
+        assert_ok!(Parameters::update(
+            Origin::root(),
+            ParameterUpdate::TreasuryFeeShare(Permill::from_percent(50))
+        ));
+        expected.treasury_fee_share = Permill::from_percent(50);
+        assert_eq!(Parameters::parameters(), expected);
+    })
+}
+
+//只有UpdateOrigin可以修改
+#[test]
+fn update_requires_update_origin() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Parameters::update(Origin::signed(1), ParameterUpdate::KittyBreedFee(0)),
+            DispatchError::BadOrigin
+        );
+    })
+}
+
+//只有UpgradeOrigin可以升级runtime
+#[test]
+fn set_code_requires_upgrade_origin() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Parameters::set_code(Origin::signed(1), vec![]),
+            DispatchError::BadOrigin
+        );
+    })
+}
+
+#[test]
+fn existential_deposit_cannot_be_zero() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Parameters::update(Origin::root(), ParameterUpdate::ExistentialDeposit(0)),
+            Error::<Test>::ZeroExistentialDeposit
+        );
+        assert_ok!(Parameters::update(
+            Origin::root(),
+            ParameterUpdate::ExistentialDeposit(2)
+        ));
+        assert_eq!(Parameters::parameters().existential_deposit, 2);
+    })
+}
+
+//ExistentialDeposit只能降低
+#[test]
+fn existential_deposit_cannot_be_raised() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Parameters::update(Origin::root(), ParameterUpdate::ExistentialDeposit(11)),
+            Error::<Test>::ExistentialDepositRaised
+        );
+        assert_ok!(Parameters::update(
+            Origin::root(),
+            ParameterUpdate::ExistentialDeposit(10)
+        ));
+        assert_ok!(Parameters::update(
+            Origin::root(),
+            ParameterUpdate::ExistentialDeposit(5)
+        ));
+        assert_noop!(
+            Parameters::update(Origin::root(), ParameterUpdate::ExistentialDeposit(10)),
+            Error::<Test>::ExistentialDepositRaised
+        );
+    })
+}
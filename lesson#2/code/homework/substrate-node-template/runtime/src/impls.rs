@@ -0,0 +1,60 @@
+//! Fee routing, treasury helpers and the parameters changed by governance.
+
+use frame_support::traits::{Contains, ContainsLengthBound, Get, Imbalance, OnUnbalanced};
+use sp_runtime::Permill;
+use sp_std::prelude::*;
+
+use crate::{AccountId, Balance, Parameters, Runtime, Treasury};
+
+/// Implements `Get` for a field of `pallet_parameters::RuntimeParameters`, so pallets read the
+/// value in storage instead of a constant.
+macro_rules! parameter_getter {
+    ($name:ident, $field:ident: $type:ty) => {
+        pub struct $name;
+
+        impl Get<$type> for $name {
+            fn get() -> $type {
+                Parameters::parameters().$field
+            }
+        }
+    };
+}
+
+parameter_getter!(ExistentialDeposit, existential_deposit: Balance);
+parameter_getter!(KittyLockToken, kitty_lock_token: Balance);
+parameter_getter!(KittyBreedFee, kitty_breed_fee: Balance);
+parameter_getter!(FreeKittyOperations, free_kitty_operations: u32);
+parameter_getter!(KittyFeeDiscount, kitty_fee_discount: Permill);
+parameter_getter!(TreasuryFeeShare, treasury_fee_share: Permill);
+
+type NegativeImbalance = pallet_balances::NegativeImbalance<Runtime>;
+
+/// Sends `TreasuryFeeShare` of the transaction fees and tips to the treasury and burns the rest.
+pub struct DealWithFees;
+
+impl OnUnbalanced<NegativeImbalance> for DealWithFees {
+    fn on_nonzero_unbalanced(amount: NegativeImbalance) {
+        let to_treasury = TreasuryFeeShare::get() * amount.peek();
+        let (to_treasury, _burnt) = amount.split(to_treasury);
+        Treasury::on_unbalanced(to_treasury);
+    }
+}
+
+/// Nobody may open tips, treasury payouts only go through proposals approved by the council.
+pub struct NoTippers;
+
+impl Contains<AccountId> for NoTippers {
+    fn sorted_members() -> Vec<AccountId> {
+        Vec::new()
+    }
+}
+
+impl ContainsLengthBound for NoTippers {
+    fn min_len() -> usize {
+        0
+    }
+
+    fn max_len() -> usize {
+        0
+    }
+}
@@ -0,0 +1,134 @@
+//! Transaction fees with free and discounted kitty operations for new players.
+
+use codec::{Decode, Encode};
+use frame_support::{
+    traits::Get,
+    weights::{DispatchInfo, Pays},
+};
+use pallet_transaction_payment::ChargeTransactionPayment;
+use sp_runtime::{
+    traits::{DispatchInfoOf, PostDispatchInfoOf, SignedExtension},
+    transaction_validity::{TransactionValidity, TransactionValidityError},
+    DispatchResult,
+};
+use sp_std::fmt;
+
+use crate::{impls, AccountId, Balance, Balances, Call, KittiesModule, Runtime, TransactionPayment};
+
+/// `ChargeTransactionPayment`, except that kitties calls are free while the signer has free
+/// operations left in the current period, see `pallet_kitties::Trait::FreeOperations`. Once
+/// they are used up, `KittyFeeDiscount` of the fee is refunded after dispatch.
+/// Only players who own a kitty or can cover the existential deposit, `KittyLockToken` and
+/// the breeding fee of the call get free or discounted calls, so accounts that could never
+/// create a kitty cannot use the quota to fill blocks for free.
+///
+/// Only the fee is waived or discounted, a tip is always paid in full. The extension encodes like
+/// `ChargeTransactionPayment` and keeps its identifier, so wallets sign for it unchanged.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct ChargeKittyFee(ChargeTransactionPayment<Runtime>);
+
+impl ChargeKittyFee {
+    /// Utility constructor, used only in client/factory code.
+    pub fn from(tip: Balance) -> Self {
+        ChargeKittyFee(ChargeTransactionPayment::from(tip))
+    }
+}
+
+impl fmt::Debug for ChargeKittyFee {
+    #[cfg(feature = "std")]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ChargeKittyFee<{:?}>", self.0)
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn fmt(&self, _: &mut fmt::Formatter) -> fmt::Result {
+        Ok(())
+    }
+}
+
+/// Whether `call` is a kitty operation of a player, which is free or discounted.
+fn is_player_kitty_call(who: &AccountId, call: &Call) -> bool {
+    let fee = match call {
+        Call::KittiesModule(pallet_kitties::Call::breed(..)) => impls::KittyBreedFee::get(),
+        Call::KittiesModule(_) => 0,
+        _ => return false,
+    };
+    let required = impls::ExistentialDeposit::get()
+        .saturating_add(impls::KittyLockToken::get())
+        .saturating_add(fee);
+    !KittiesModule::user_kitties(who).is_empty() || Balances::free_balance(who) >= required
+}
+
+/// `info` with the fee waived.
+fn free(info: &DispatchInfo) -> DispatchInfo {
+    DispatchInfo { pays_fee: Pays::No, ..*info }
+}
+
+impl SignedExtension for ChargeKittyFee {
+    const IDENTIFIER: &'static str = <ChargeTransactionPayment<Runtime> as SignedExtension>::IDENTIFIER;
+    type AccountId = AccountId;
+    type Call = Call;
+    type AdditionalSigned = ();
+    /// The inner `Pre` and whether the fee is discounted.
+    type Pre = (<ChargeTransactionPayment<Runtime> as SignedExtension>::Pre, bool);
+
+    fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+        Ok(())
+    }
+
+    fn validate(
+        &self,
+        who: &Self::AccountId,
+        call: &Self::Call,
+        info: &DispatchInfoOf<Self::Call>,
+        len: usize,
+    ) -> TransactionValidity {
+        // Only checks the quota, it is used up in `pre_dispatch`.
+        if is_player_kitty_call(who, call) && KittiesModule::free_operations_left(who) > 0 {
+            self.0.validate(who, call, &free(info), len)
+        } else {
+            self.0.validate(who, call, info, len)
+        }
+    }
+
+    fn pre_dispatch(
+        self,
+        who: &Self::AccountId,
+        call: &Self::Call,
+        info: &DispatchInfoOf<Self::Call>,
+        len: usize,
+    ) -> Result<Self::Pre, TransactionValidityError> {
+        let player = is_player_kitty_call(who, call);
+        if player && KittiesModule::use_free_operation(who) {
+            Ok((self.0.pre_dispatch(who, call, &free(info), len)?, false))
+        } else {
+            Ok((self.0.pre_dispatch(who, call, info, len)?, player))
+        }
+    }
+
+    fn post_dispatch(
+        pre: Self::Pre,
+        info: &DispatchInfoOf<Self::Call>,
+        post_info: &PostDispatchInfoOf<Self::Call>,
+        len: usize,
+        result: &DispatchResult,
+    ) -> Result<(), TransactionValidityError> {
+        let ((tip, who, imbalance, fee), discounted) = pre;
+        // The withdrawn fee above the actual one is refunded, so adding the discount refunds it
+        // too. A free call only paid the tip, so nothing is refunded.
+        let fee = if discounted {
+            let actual_fee = TransactionPayment::compute_actual_fee(len as u32, info, post_info, tip);
+            fee.saturating_add(impls::KittyFeeDiscount::get() * actual_fee.saturating_sub(tip))
+        } else {
+            fee
+        };
+        <ChargeTransactionPayment<Runtime> as SignedExtension>::post_dispatch(
+            (tip, who, imbalance, fee),
+            info,
+            post_info,
+            len,
+            result,
+        )
+    }
+}
+
@@ -6,18 +6,23 @@
 #[cfg(feature = "std")]
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 
+use codec::{Decode, Encode};
 use pallet_grandpa::fg_primitives;
 use pallet_grandpa::{AuthorityId as GrandpaId, AuthorityList as GrandpaAuthorityList};
 use sp_api::impl_runtime_apis;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
-use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
+use sp_core::{
+    crypto::KeyTypeId,
+    u32_trait::{_1, _2, _3, _4, _5},
+    OpaqueMetadata,
+};
 use sp_runtime::traits::{
     BlakeTwo256, Block as BlockT, IdentifyAccount, IdentityLookup, NumberFor, Saturating, Verify,
 };
 use sp_runtime::{
     create_runtime_str, generic, impl_opaque_keys,
     transaction_validity::{TransactionSource, TransactionValidity},
-    ApplyExtrinsicResult, MultiSignature,
+    ApplyExtrinsicResult, ModuleId, MultiSignature, RuntimeDebug,
 };
 use sp_std::prelude::*;
 #[cfg(feature = "std")]
@@ -27,7 +32,7 @@ use sp_version::RuntimeVersion;
 // A few exports that help ease life for downstream crates.
 pub use frame_support::{
     construct_runtime, parameter_types,
-    traits::{KeyOwnerProofSystem, Randomness},
+    traits::{InstanceFilter, KeyOwnerProofSystem, Randomness},
     weights::{
         constants::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight, WEIGHT_PER_SECOND},
         IdentityFee, Weight,
@@ -38,7 +43,7 @@ pub use pallet_balances::Call as BalancesCall;
 pub use pallet_timestamp::Call as TimestampCall;
 #[cfg(any(feature = "std", test))]
 pub use sp_runtime::BuildStorage;
-pub use sp_runtime::{Perbill, Permill};
+pub use sp_runtime::{Perbill, Percent, Permill};
 
 pub use pallet_kitties;
 /// Import the template pallet.
@@ -231,7 +236,6 @@ impl pallet_timestamp::Trait for Runtime {
 }
 
 parameter_types! {
-    pub const ExistentialDeposit: u128 = 500;
     pub const MaxLocks: u32 = 50;
 }
 
@@ -242,7 +246,7 @@ impl pallet_balances::Trait for Runtime {
     /// The ubiquitous event type.
     type Event = Event;
     type DustRemoval = ();
-    type ExistentialDeposit = ExistentialDeposit;
+    type ExistentialDeposit = impls::ExistentialDeposit;
     type AccountStore = System;
     type WeightInfo = ();
 }
@@ -253,15 +257,193 @@ parameter_types! {
 
 impl pallet_transaction_payment::Trait for Runtime {
     type Currency = Balances;
-    type OnTransactionPayment = ();
+    type OnTransactionPayment = impls::DealWithFees;
     type TransactionByteFee = TransactionByteFee;
     type WeightToFee = IdentityFee<Balance>;
     type FeeMultiplierUpdate = ();
 }
 
-impl pallet_sudo::Trait for Runtime {
+parameter_types! {
+    pub const TreasuryModuleId: ModuleId = ModuleId(*b"py/trsry");
+    pub const ProposalBond: Permill = Permill::from_percent(5);
+    pub const ProposalBondMinimum: Balance = 1_000;
+    pub const SpendPeriod: BlockNumber = DAYS;
+    pub const Burn: Permill = Permill::from_percent(0);
+    pub const TipCountdown: BlockNumber = DAYS;
+    pub const TipFindersFee: Percent = Percent::from_percent(20);
+    pub const TipReportDepositBase: Balance = 1_000;
+    pub const DataDepositPerByte: Balance = 1;
+    pub const BountyDepositBase: Balance = 1_000;
+    pub const BountyDepositPayoutDelay: BlockNumber = DAYS;
+    pub const BountyUpdatePeriod: BlockNumber = 14 * DAYS;
+    pub const BountyCuratorDeposit: Permill = Permill::from_percent(50);
+    pub const BountyValueMinimum: Balance = 10_000;
+    pub const MaximumReasonLength: u32 = 16384;
+}
+
+impl pallet_treasury::Trait for Runtime {
+    type ModuleId = TreasuryModuleId;
+    type Currency = Balances;
+    /// Spending proposals are approved by 3/5 and rejected by 1/2 of the council.
+    type ApproveOrigin = pallet_collective::EnsureProportionAtLeast<_3, _5, AccountId, CouncilCollective>;
+    type RejectOrigin = pallet_collective::EnsureProportionMoreThan<_1, _2, AccountId, CouncilCollective>;
+    type Tippers = impls::NoTippers;
+    type TipCountdown = TipCountdown;
+    type TipFindersFee = TipFindersFee;
+    type TipReportDepositBase = TipReportDepositBase;
+    type DataDepositPerByte = DataDepositPerByte;
+    type Event = Event;
+    type OnSlash = Treasury;
+    type ProposalBond = ProposalBond;
+    type ProposalBondMinimum = ProposalBondMinimum;
+    type SpendPeriod = SpendPeriod;
+    type Burn = Burn;
+    type BountyDepositBase = BountyDepositBase;
+    type BountyDepositPayoutDelay = BountyDepositPayoutDelay;
+    type BountyUpdatePeriod = BountyUpdatePeriod;
+    type BountyCuratorDeposit = BountyCuratorDeposit;
+    type BountyValueMinimum = BountyValueMinimum;
+    type MaximumReasonLength = MaximumReasonLength;
+    type BurnDestination = ();
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    pub const CouncilMotionDuration: BlockNumber = 3 * DAYS;
+    pub const CouncilMaxProposals: u32 = 100;
+    pub const CouncilMaxMembers: u32 = 100;
+}
+
+type CouncilCollective = pallet_collective::Instance1;
+impl pallet_collective::Trait<CouncilCollective> for Runtime {
+    type Origin = Origin;
+    type Proposal = Call;
+    type Event = Event;
+    type MotionDuration = CouncilMotionDuration;
+    type MaxProposals = CouncilMaxProposals;
+    type MaxMembers = CouncilMaxMembers;
+    type DefaultVote = pallet_collective::PrimeDefaultVote;
+    type WeightInfo = ();
+}
+
+/// A motion passed by 2/3 of the council.
+type EnsureTwoThirdsCouncil =
+    pallet_collective::EnsureProportionAtLeast<_2, _3, AccountId, CouncilCollective>;
+/// A motion passed by 3/4 of the council.
+type EnsureThreeQuartersCouncil =
+    pallet_collective::EnsureProportionAtLeast<_3, _4, AccountId, CouncilCollective>;
+
+impl pallet_membership::Trait<pallet_membership::Instance1> for Runtime {
+    type Event = Event;
+    type AddOrigin = EnsureTwoThirdsCouncil;
+    type RemoveOrigin = EnsureTwoThirdsCouncil;
+    type SwapOrigin = EnsureTwoThirdsCouncil;
+    type ResetOrigin = EnsureTwoThirdsCouncil;
+    type PrimeOrigin = EnsureTwoThirdsCouncil;
+    type MembershipInitialized = Council;
+    type MembershipChanged = Council;
+}
+
+parameter_types! {
+    //治理修改之前的参数
+    pub DefaultParameters: pallet_parameters::RuntimeParameters<Balance> =
+        pallet_parameters::RuntimeParameters {
+            existential_deposit: 500,
+            kitty_lock_token: 5, //create和transfer时需要质押的token
+            kitty_breed_fee: 10, //繁殖费，进入国库
+            free_kitty_operations: 10, //每个账户每天免交易费的kitty操作数
+            kitty_fee_discount: Permill::from_percent(50), //免费次数用完后kitty操作的交易费折扣
+            treasury_fee_share: Permill::from_percent(20), //交易费和小费进入国库的比例，其余销毁
+        };
+}
+
+impl pallet_parameters::Trait for Runtime {
+    type Event = Event;
+    type Balance = Balance;
+    type UpdateOrigin = EnsureTwoThirdsCouncil;
+    /// There is no sudo key, runtime upgrades need 3/4 of the council.
+    type UpgradeOrigin = EnsureThreeQuartersCouncil;
+    type Defaults = DefaultParameters;
+}
+
+parameter_types! {
+    pub const MultisigDepositBase: Balance = 1_000;
+    pub const MultisigDepositFactor: Balance = 100;
+    pub const MaxSignatories: u16 = 20;
+}
+
+/// Guild and team accounts share custody of kitties through multisig accounts.
+impl pallet_multisig::Trait for Runtime {
     type Event = Event;
     type Call = Call;
+    type Currency = Balances;
+    type DepositBase = MultisigDepositBase;
+    type DepositFactor = MultisigDepositFactor;
+    type MaxSignatories = MaxSignatories;
+    type WeightInfo = ();
+}
+
+/// What a proxy may do for the account that added it.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Encode, Decode, RuntimeDebug)]
+pub enum ProxyType {
+    /// Any call.
+    Any,
+    /// Any kitties call, e.g. to manage the kitties of a guild.
+    Kitties,
+    /// Only `breed`, the kitties can't be transferred away.
+    Breeding,
+}
+
+impl Default for ProxyType {
+    fn default() -> Self {
+        ProxyType::Any
+    }
+}
+
+impl InstanceFilter<Call> for ProxyType {
+    fn filter(&self, c: &Call) -> bool {
+        match self {
+            ProxyType::Any => true,
+            ProxyType::Kitties => matches!(c, Call::KittiesModule(..)),
+            ProxyType::Breeding => {
+                matches!(c, Call::KittiesModule(pallet_kitties::Call::breed(..)))
+            }
+        }
+    }
+
+    fn is_superset(&self, o: &Self) -> bool {
+        match (self, o) {
+            (x, y) if x == y => true,
+            (ProxyType::Any, _) => true,
+            (_, ProxyType::Any) => false,
+            (ProxyType::Kitties, ProxyType::Breeding) => true,
+            _ => false,
+        }
+    }
+}
+
+parameter_types! {
+    pub const ProxyDepositBase: Balance = 1_000;
+    pub const ProxyDepositFactor: Balance = 100;
+    pub const MaxProxies: u16 = 32;
+    pub const MaxPending: u32 = 32;
+    pub const AnnouncementDepositBase: Balance = 1_000;
+    pub const AnnouncementDepositFactor: Balance = 200;
+}
+
+impl pallet_proxy::Trait for Runtime {
+    type Event = Event;
+    type Call = Call;
+    type Currency = Balances;
+    type ProxyType = ProxyType;
+    type ProxyDepositBase = ProxyDepositBase;
+    type ProxyDepositFactor = ProxyDepositFactor;
+    type MaxProxies = MaxProxies;
+    type WeightInfo = ();
+    type MaxPending = MaxPending;
+    type CallHasher = BlakeTwo256;
+    type AnnouncementDepositBase = AnnouncementDepositBase;
+    type AnnouncementDepositFactor = AnnouncementDepositFactor;
 }
 
 /// Configure the template pallet in pallets/template.
@@ -271,14 +453,18 @@ impl pallet_template::Trait for Runtime {
 
 pub type KittyIndex = u32;
 parameter_types! {
-    pub const KittyLockToken: Balance = 5; //create和transfer时需要质押的token
+    pub const FreeKittyOperationsPeriod: BlockNumber = DAYS;
 }
 impl pallet_kitties::Trait for Runtime {
     type Event = Event;
     type Randomness = RandomnessCollectiveFlip;
     type KittyIndex = KittyIndex;
-    type KittyLockToken = KittyLockToken;
+    type KittyLockToken = impls::KittyLockToken;
     type Currency = Balances;
+    type FreeOperations = impls::FreeKittyOperations;
+    type FreeOperationsPeriod = FreeKittyOperationsPeriod;
+    type BreedFee = impls::KittyBreedFee;
+    type OnFee = Treasury;
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
@@ -295,13 +481,34 @@ construct_runtime!(
         Grandpa: pallet_grandpa::{Module, Call, Storage, Config, Event},
         Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
         TransactionPayment: pallet_transaction_payment::{Module, Storage},
-        Sudo: pallet_sudo::{Module, Call, Config<T>, Storage, Event<T>},
+        Treasury: pallet_treasury::{Module, Call, Storage, Config, Event<T>},
+        Council: pallet_collective::<Instance1>::{Module, Call, Storage, Origin<T>, Event<T>, Config<T>},
+        CouncilMembership: pallet_membership::<Instance1>::{Module, Call, Storage, Event<T>, Config<T>},
+        Parameters: pallet_parameters::{Module, Call, Storage, Event<T>},
+        Multisig: pallet_multisig::{Module, Call, Storage, Event<T>},
+        Proxy: pallet_proxy::{Module, Call, Storage, Event<T>},
         // Include the custom logic from the template pallet in the runtime.
         TemplateModule: pallet_template::{Module, Call, Storage, Event<T>},
-        KittiesModule: pallet_kitties::{Module, Call, Storage, Event<T>},
+        KittiesModule: pallet_kitties::{Module, Call, Storage, Event<T>, Config<T>},
     }
 );
 
+pub mod impls;
+pub mod kitty_fee;
+pub mod upgrade_check;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests_kitty_fee;
+
+#[cfg(test)]
+mod tests_custody;
+
+#[cfg(test)]
+mod tests_governance;
+
 /// The address format for describing accounts.
 pub type Address = AccountId;
 /// Block header type as expected by this runtime.
@@ -320,7 +527,7 @@ pub type SignedExtra = (
     frame_system::CheckEra<Runtime>,
     frame_system::CheckNonce<Runtime>,
     frame_system::CheckWeight<Runtime>,
-    pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+    kitty_fee::ChargeKittyFee,
 );
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
@@ -461,6 +668,38 @@ impl_runtime_apis! {
         }
     }
 
+    impl pallet_kitties_runtime_api::KittiesApi<Block, AccountId, KittyIndex> for Runtime {
+        fn kitty(kitty_id: KittyIndex) -> Option<pallet_kitties::KittyInfo<AccountId, KittyIndex>> {
+            KittiesModule::kitty_info(kitty_id)
+        }
+
+        fn owner(kitty_id: KittyIndex) -> Option<AccountId> {
+            KittiesModule::kitty_owners(kitty_id)
+        }
+
+        fn genome(kitty_id: KittyIndex) -> Option<pallet_kitties::KittyGenome> {
+            KittiesModule::kitty_genome(kitty_id)
+        }
+
+        fn kitties_of(owner: AccountId) -> Vec<KittyIndex> {
+            KittiesModule::user_kitties(owner)
+        }
+
+        fn kitties_count() -> KittyIndex {
+            KittiesModule::kitties_count()
+        }
+
+        fn try_state() -> Vec<pallet_kitties::Violation<AccountId, KittyIndex>> {
+            KittiesModule::do_try_state()
+        }
+    }
+
+    impl upgrade_check::TryUpgradeApi<Block> for Runtime {
+        fn try_upgrade() -> upgrade_check::UpgradeReport {
+            upgrade_check::try_upgrade()
+        }
+    }
+
     #[cfg(feature = "runtime-benchmarks")]
     impl frame_benchmarking::Benchmark<Block> for Runtime {
         fn dispatch_benchmark(
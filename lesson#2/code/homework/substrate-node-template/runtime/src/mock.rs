@@ -0,0 +1,20 @@
+//! Test externalities shared by the runtime tests.
+
+use crate::*;
+
+pub fn account(n: u8) -> AccountId {
+    AccountId::from([n; 32])
+}
+
+/// Genesis with `balances` endowed, at block 1 so events are recorded.
+pub fn new_test_ext(balances: Vec<(AccountId, Balance)>) -> sp_io::TestExternalities {
+    let mut t = frame_system::GenesisConfig::default()
+        .build_storage::<Runtime>()
+        .unwrap();
+    pallet_balances::GenesisConfig::<Runtime> { balances }
+        .assimilate_storage(&mut t)
+        .unwrap();
+    let mut ext = sp_io::TestExternalities::new(t);
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
@@ -0,0 +1,198 @@
+use crate::mock::{account, new_test_ext};
+use crate::*;
+use codec::Encode;
+use frame_support::{assert_noop, assert_ok, traits::InstanceFilter};
+use sp_io::hashing::blake2_256;
+use sp_runtime::DispatchError;
+
+const ENDOWMENT: Balance = 10_000;
+
+fn kitty_call(call: pallet_kitties::Call<Runtime>) -> Call {
+    Call::KittiesModule(call)
+}
+
+fn has_event(event: Event) -> bool {
+    System::events().iter().any(|record| record.event == event)
+}
+
+fn last_event() -> Event {
+    System::events().pop().expect("an event was deposited").event
+}
+
+fn endowed(n: u8) -> Vec<(AccountId, Balance)> {
+    (1..=n).map(|i| (account(i), ENDOWMENT)).collect()
+}
+
+/// The 2 of 3 multisig account of accounts 1, 2 and 3, owning kitty 0.
+fn setup_multisig() -> AccountId {
+    let guild = Multisig::multi_account_id(&[account(1), account(2), account(3)], 2);
+    assert_ok!(Balances::transfer(Origin::signed(account(4)), guild.clone(), 1_000));
+    assert_ok!(KittiesModule::create(Origin::signed(account(1))));
+    assert_ok!(KittiesModule::transfer(Origin::signed(account(1)), guild.clone(), 0));
+    guild
+}
+
+#[test]
+fn proxy_types_filter_calls() {
+    let breed = kitty_call(pallet_kitties::Call::breed(0, 1));
+    let transfer = kitty_call(pallet_kitties::Call::transfer(account(2), 0));
+    let balance_transfer = Call::Balances(pallet_balances::Call::transfer(account(2), 10));
+
+    assert!(ProxyType::Any.filter(&balance_transfer));
+    assert!(ProxyType::Kitties.filter(&breed));
+    assert!(ProxyType::Kitties.filter(&transfer));
+    assert!(!ProxyType::Kitties.filter(&balance_transfer));
+    assert!(ProxyType::Breeding.filter(&breed));
+    assert!(!ProxyType::Breeding.filter(&transfer));
+    assert!(!ProxyType::Breeding.filter(&balance_transfer));
+
+    assert!(ProxyType::Any.is_superset(&ProxyType::Kitties));
+    assert!(ProxyType::Kitties.is_superset(&ProxyType::Breeding));
+    assert!(!ProxyType::Breeding.is_superset(&ProxyType::Kitties));
+    assert!(!ProxyType::Kitties.is_superset(&ProxyType::Any));
+}
+
+#[test]
+fn multisig_transfers_kitty_with_two_of_three() {
+    new_test_ext(endowed(4)).execute_with(|| {
+        let guild = setup_multisig();
+        let call = kitty_call(pallet_kitties::Call::transfer(account(4), 0)).encode();
+
+        // the first approval is only recorded
+        assert_ok!(Multisig::as_multi(
+            Origin::signed(account(1)),
+            2,
+            vec![account(2), account(3)],
+            None,
+            call.clone(),
+            false,
+            0
+        ));
+        assert_eq!(KittiesModule::kitty_owners(0), Some(guild.clone()));
+
+        // the second one dispatches the transfer as the guild
+        let timepoint = Multisig::timepoint();
+        assert_ok!(Multisig::as_multi(
+            Origin::signed(account(3)),
+            2,
+            vec![account(1), account(2)],
+            Some(timepoint),
+            call,
+            false,
+            0
+        ));
+        assert_eq!(KittiesModule::kitty_owners(0), Some(account(4)));
+        assert!(KittiesModule::user_kitties(&guild).is_empty());
+        assert!(has_event(Event::pallet_kitties(pallet_kitties::RawEvent::Transferred(
+            guild,
+            account(4),
+            0
+        ))));
+        assert_eq!(KittiesModule::do_try_state(), vec![]);
+    });
+}
+
+#[test]
+fn multisig_kitty_needs_signatories() {
+    new_test_ext(endowed(4)).execute_with(|| {
+        let guild = setup_multisig();
+        let call = kitty_call(pallet_kitties::Call::transfer(account(4), 0)).encode();
+
+        assert_ok!(Multisig::approve_as_multi(
+            Origin::signed(account(1)),
+            2,
+            vec![account(2), account(3)],
+            None,
+            blake2_256(&call),
+            0
+        ));
+        // account 4 is no signatory, so this is another multisig account
+        assert_ok!(Multisig::as_multi(
+            Origin::signed(account(4)),
+            2,
+            vec![account(1), account(2)],
+            None,
+            call,
+            false,
+            0
+        ));
+        assert_eq!(KittiesModule::kitty_owners(0), Some(guild));
+
+        assert_noop!(
+            KittiesModule::transfer(Origin::signed(account(1)), account(4), 0),
+            pallet_kitties::Error::<Runtime>::NotKittyOwner
+        );
+    });
+}
+
+#[test]
+fn breeding_proxy_can_only_breed() {
+    new_test_ext(endowed(2)).execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(account(1))));
+        assert_ok!(KittiesModule::create(Origin::signed(account(1))));
+        assert_ok!(Proxy::add_proxy(Origin::signed(account(1)), account(2), ProxyType::Breeding, 0));
+
+        // the child belongs to account 1, which also pays the breeding fee
+        assert_ok!(Proxy::proxy(
+            Origin::signed(account(2)),
+            account(1),
+            None,
+            Box::new(kitty_call(pallet_kitties::Call::breed(0, 1)))
+        ));
+        assert_eq!(KittiesModule::kitty_owners(2), Some(account(1)));
+        assert_eq!(Balances::free_balance(&account(2)), ENDOWMENT);
+
+        assert_ok!(Proxy::proxy(
+            Origin::signed(account(2)),
+            account(1),
+            None,
+            Box::new(kitty_call(pallet_kitties::Call::transfer(account(2), 0)))
+        ));
+        assert_eq!(
+            last_event(),
+            Event::pallet_proxy(pallet_proxy::RawEvent::ProxyExecuted(Err(DispatchError::BadOrigin)))
+        );
+        assert_eq!(KittiesModule::kitty_owners(0), Some(account(1)));
+
+        // calls of other pallets are filtered too
+        assert_ok!(Proxy::proxy(
+            Origin::signed(account(2)),
+            account(1),
+            None,
+            Box::new(Call::Balances(pallet_balances::Call::transfer(account(2), 10)))
+        ));
+        assert_eq!(Balances::free_balance(&account(2)), ENDOWMENT);
+        assert_eq!(KittiesModule::do_try_state(), vec![]);
+    });
+}
+
+#[test]
+fn kitties_proxy_can_transfer() {
+    new_test_ext(endowed(3)).execute_with(|| {
+        assert_ok!(KittiesModule::create(Origin::signed(account(1))));
+        assert_ok!(Proxy::add_proxy(Origin::signed(account(1)), account(2), ProxyType::Kitties, 0));
+
+        assert_ok!(Proxy::proxy(
+            Origin::signed(account(2)),
+            account(1),
+            None,
+            Box::new(kitty_call(pallet_kitties::Call::transfer(account(3), 0)))
+        ));
+        assert_eq!(KittiesModule::kitty_owners(0), Some(account(3)));
+        assert!(has_event(Event::pallet_kitties(pallet_kitties::RawEvent::Transferred(
+            account(1),
+            account(3),
+            0
+        ))));
+
+        // the proxy cannot move a kitty account 1 does not own
+        assert_ok!(Proxy::proxy(
+            Origin::signed(account(2)),
+            account(1),
+            None,
+            Box::new(kitty_call(pallet_kitties::Call::transfer(account(2), 0)))
+        ));
+        assert_eq!(KittiesModule::kitty_owners(0), Some(account(3)));
+        assert_eq!(KittiesModule::do_try_state(), vec![]);
+    });
+}
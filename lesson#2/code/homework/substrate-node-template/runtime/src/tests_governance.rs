@@ -0,0 +1,78 @@
+use crate::mock::{account, new_test_ext};
+use crate::*;
+use codec::Encode;
+use frame_support::{assert_ok, traits::Get, weights::GetDispatchInfo};
+use pallet_parameters::ParameterUpdate;
+use sp_runtime::{traits::Hash, DispatchError, DispatchResult};
+
+fn council() -> Vec<AccountId> {
+    vec![account(1), account(2), account(3)]
+}
+
+fn new_council_ext() -> sp_io::TestExternalities {
+    let mut ext = new_test_ext(council().into_iter().map(|who| (who, 10_000)).collect());
+    ext.execute_with(|| {
+        assert_ok!(Council::set_members(Origin::root(), council(), None, 0));
+    });
+    ext
+}
+
+/// Propose `call` as account 1, vote aye with `ayes`, then close the motion.
+fn pass_motion(call: Call, threshold: u32, ayes: &[u8]) -> <Runtime as frame_system::Trait>::Hash {
+    let hash = BlakeTwo256::hash_of(&call);
+    let length = call.encode().len() as u32;
+    let weight = call.get_dispatch_info().weight;
+    let index = Council::proposal_count();
+
+    assert_ok!(Council::propose(Origin::signed(account(1)), threshold, Box::new(call), length));
+    for n in ayes {
+        assert_ok!(Council::vote(Origin::signed(account(*n)), hash, index, true));
+    }
+    assert_ok!(Council::close(Origin::signed(account(1)), hash, index, weight, length));
+    hash
+}
+
+fn executed(hash: <Runtime as frame_system::Trait>::Hash, result: DispatchResult) -> bool {
+    let event = Event::pallet_collective_Instance1(pallet_collective::RawEvent::Executed(hash, result));
+    System::events().iter().any(|record| record.event == event)
+}
+
+#[test]
+fn council_motion_updates_parameters() {
+    new_council_ext().execute_with(|| {
+        let call = Call::Parameters(pallet_parameters::Call::update(ParameterUpdate::KittyLockToken(7)));
+
+        // the proposer counts as an aye, 2 of 3 is enough
+        let hash = pass_motion(call, 2, &[2]);
+
+        assert!(executed(hash, Ok(())));
+        assert_eq!(Parameters::parameters().kitty_lock_token, 7);
+        assert_eq!(<Runtime as pallet_kitties::Trait>::KittyLockToken::get(), 7);
+    });
+}
+
+#[test]
+fn parameters_need_two_thirds_of_the_council() {
+    new_council_ext().execute_with(|| {
+        let call = Call::Parameters(pallet_parameters::Call::update(ParameterUpdate::KittyLockToken(7)));
+
+        // a threshold of 1 dispatches right away with a single aye
+        let hash = BlakeTwo256::hash_of(&call);
+        let length = call.encode().len() as u32;
+        assert_ok!(Council::propose(Origin::signed(account(1)), 1, Box::new(call), length));
+
+        assert!(executed(hash, Err(DispatchError::BadOrigin)));
+        assert_eq!(Parameters::parameters(), DefaultParameters::get());
+    });
+}
+
+#[test]
+fn runtime_upgrades_need_three_quarters_of_the_council() {
+    new_council_ext().execute_with(|| {
+        let call = Call::Parameters(pallet_parameters::Call::set_code(vec![]));
+
+        let hash = pass_motion(call, 2, &[2]);
+
+        assert!(executed(hash, Err(DispatchError::BadOrigin)));
+    });
+}
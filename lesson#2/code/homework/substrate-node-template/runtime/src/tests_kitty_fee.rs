@@ -0,0 +1,173 @@
+use crate::kitty_fee::ChargeKittyFee;
+use crate::mock::{account, new_test_ext as new_ext};
+use crate::*;
+use frame_support::{
+    traits::Get,
+    weights::{DispatchClass, DispatchInfo, Pays, PostDispatchInfo},
+};
+use sp_runtime::{
+    traits::SignedExtension,
+    transaction_validity::{InvalidTransaction, TransactionValidityError},
+};
+
+const RICH: Balance = 1_000_000_000_000;
+const LEN: usize = 10;
+
+fn alice() -> AccountId {
+    account(1)
+}
+
+/// Holds no balance at all.
+fn bob() -> AccountId {
+    account(2)
+}
+
+/// Holds the existential deposit plus the kitty lock, but not the breeding fee.
+fn charlie() -> AccountId {
+    account(3)
+}
+
+fn player_balance() -> Balance {
+    impls::ExistentialDeposit::get() + impls::KittyLockToken::get()
+}
+
+fn new_test_ext() -> sp_io::TestExternalities {
+    new_ext(vec![(alice(), RICH), (charlie(), player_balance())])
+}
+
+fn kitty_call() -> Call {
+    Call::KittiesModule(pallet_kitties::Call::create())
+}
+
+fn breed_call() -> Call {
+    Call::KittiesModule(pallet_kitties::Call::breed(0, 1))
+}
+
+fn remark_call() -> Call {
+    Call::System(frame_system::Call::remark(vec![]))
+}
+
+fn info() -> DispatchInfo {
+    DispatchInfo { weight: 100, class: DispatchClass::Normal, pays_fee: Pays::Yes }
+}
+
+/// Run `pre_dispatch` and `post_dispatch` of a successful call.
+fn charge(who: &AccountId, call: &Call, tip: Balance) -> Result<(), TransactionValidityError> {
+    let pre = ChargeKittyFee::from(tip).pre_dispatch(who, call, &info(), LEN)?;
+    let post_info: PostDispatchInfo = ().into();
+    ChargeKittyFee::post_dispatch(pre, &info(), &post_info, LEN, &Ok(()))
+}
+
+fn use_up_free_operations(who: &AccountId) {
+    while KittiesModule::use_free_operation(who) {}
+}
+
+#[test]
+fn free_kitty_calls_only_pay_the_tip() {
+    new_test_ext().execute_with(|| {
+        let free_operations = KittiesModule::free_operations_left(&alice());
+        assert!(free_operations > 0);
+
+        assert_eq!(charge(&alice(), &kitty_call(), 0), Ok(()));
+        assert_eq!(Balances::free_balance(&alice()), RICH);
+
+        // only the tip is paid
+        assert_eq!(charge(&alice(), &kitty_call(), 7), Ok(()));
+        assert_eq!(Balances::free_balance(&alice()), RICH - 7);
+        assert_eq!(KittiesModule::free_operations_left(&alice()), free_operations - 2);
+    });
+}
+
+#[test]
+fn validate_keeps_the_quota() {
+    new_test_ext().execute_with(|| {
+        let free_operations = KittiesModule::free_operations_left(&alice());
+        let validity = ChargeKittyFee::from(0).validate(&alice(), &kitty_call(), &info(), LEN);
+        assert!(validity.is_ok());
+        assert_eq!(KittiesModule::free_operations_left(&alice()), free_operations);
+        assert_eq!(Balances::free_balance(&alice()), RICH);
+    });
+}
+
+#[test]
+fn exhausted_quota_pays_a_discounted_fee() {
+    new_test_ext().execute_with(|| {
+        use_up_free_operations(&alice());
+        let fee = TransactionPayment::compute_fee(LEN as u32, &info(), 7);
+        let discount = impls::KittyFeeDiscount::get() * (fee - 7);
+        assert!(discount > 0);
+
+        assert!(ChargeKittyFee::from(7).validate(&alice(), &kitty_call(), &info(), LEN).is_ok());
+        assert_eq!(charge(&alice(), &kitty_call(), 7), Ok(()));
+        // the tip is not discounted
+        assert_eq!(Balances::free_balance(&alice()), RICH - fee + discount);
+    });
+}
+
+#[test]
+fn other_calls_pay_the_fee() {
+    new_test_ext().execute_with(|| {
+        let free_operations = KittiesModule::free_operations_left(&alice());
+        let fee = TransactionPayment::compute_fee(LEN as u32, &info(), 0);
+
+        assert_eq!(charge(&alice(), &remark_call(), 0), Ok(()));
+        assert_eq!(Balances::free_balance(&alice()), RICH - fee);
+        assert_eq!(KittiesModule::free_operations_left(&alice()), free_operations);
+    });
+}
+
+#[test]
+fn accounts_without_funds_or_kitties_are_not_free() {
+    new_test_ext().execute_with(|| {
+        let free_operations = KittiesModule::free_operations_left(&bob());
+        let payment_error = || Err(TransactionValidityError::Invalid(InvalidTransaction::Payment));
+
+        assert_eq!(
+            ChargeKittyFee::from(0).validate(&bob(), &kitty_call(), &info(), LEN).map(|_| ()),
+            payment_error()
+        );
+        assert_eq!(charge(&bob(), &kitty_call(), 0), payment_error());
+        assert_eq!(KittiesModule::free_operations_left(&bob()), free_operations);
+    });
+}
+
+#[test]
+fn players_cover_the_deposit_and_the_lock() {
+    new_ext(vec![(charlie(), player_balance() - 1)]).execute_with(|| {
+        let free_operations = KittiesModule::free_operations_left(&charlie());
+
+        // the fee is far above the balance, a free call would have passed
+        assert!(charge(&charlie(), &kitty_call(), 0).is_err());
+        assert_eq!(KittiesModule::free_operations_left(&charlie()), free_operations);
+    });
+
+    new_test_ext().execute_with(|| {
+        assert_eq!(charge(&charlie(), &kitty_call(), 0), Ok(()));
+        assert_eq!(Balances::free_balance(&charlie()), player_balance());
+    });
+}
+
+#[test]
+fn breeding_players_also_cover_the_breed_fee() {
+    new_test_ext().execute_with(|| {
+        let free_operations = KittiesModule::free_operations_left(&charlie());
+
+        assert!(charge(&charlie(), &breed_call(), 0).is_err());
+        assert_eq!(KittiesModule::free_operations_left(&charlie()), free_operations);
+
+        assert_eq!(charge(&alice(), &breed_call(), 0), Ok(()));
+        assert_eq!(Balances::free_balance(&alice()), RICH);
+    });
+}
+
+#[test]
+fn kitty_owners_stay_free() {
+    new_test_ext().execute_with(|| {
+        // bob cannot cover the lock, but already owns a kitty
+        pallet_kitties::UserKitties::<Runtime>::insert(&bob(), vec![0]);
+
+        assert!(ChargeKittyFee::from(0).validate(&bob(), &kitty_call(), &info(), LEN).is_ok());
+        assert_eq!(charge(&bob(), &kitty_call(), 0), Ok(()));
+        assert_eq!(Balances::free_balance(&bob()), 0);
+    });
+}
@@ -0,0 +1,41 @@
+//! Checks a runtime upgrade against existing state, used by the node's `try-upgrade` command.
+
+use codec::{Decode, Encode};
+use frame_support::{traits::OnRuntimeUpgrade, weights::Weight};
+use sp_runtime::RuntimeDebug;
+use sp_std::prelude::*;
+
+use crate::{AccountId, AllModules, KittiesModule, KittyIndex, Runtime};
+
+/// A broken invariant found after the upgrade.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum Violation {
+    Kitties(pallet_kitties::Violation<AccountId, KittyIndex>),
+}
+
+/// The outcome of `TryUpgradeApi::try_upgrade`.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct UpgradeReport {
+    /// The weight `on_runtime_upgrade` reported for all pallets.
+    pub weight: Weight,
+    pub violations: Vec<Violation>,
+}
+
+sp_api::decl_runtime_apis! {
+    /// Runs the upgrade hooks of this runtime and checks the pallet invariants afterwards.
+    ///
+    /// Only meant to be called on a throwaway copy of the state.
+    pub trait TryUpgradeApi {
+        fn try_upgrade() -> UpgradeReport;
+    }
+}
+
+/// Run `on_runtime_upgrade` of all pallets, then every pallet's `post_upgrade` check.
+pub fn try_upgrade() -> UpgradeReport {
+    let weight = <(frame_system::Module<Runtime>, AllModules) as OnRuntimeUpgrade>::on_runtime_upgrade();
+
+    let mut violations = Vec::new();
+    violations.extend(KittiesModule::post_upgrade().into_iter().map(Violation::Kitties));
+
+    UpgradeReport { weight, violations }
+}
@@ -1,10 +1,13 @@
 import React, { useState } from 'react';
 import { Form, Input, Grid } from 'semantic-ui-react';
+import { useSubstrate } from './substrate-lib';
 import { TxButton } from './substrate-lib/components';
 
 export default function Main (props) {
+  const { api } = useSubstrate();
   const [status, setStatus] = useState('');
-  const [proposal, setProposal] = useState({});
+  const [code, setCode] = useState(null);
+  const [threshold, setThreshold] = useState('');
   const { accountPair } = props;
 
   const bufferToHex = buffer => {
@@ -17,12 +20,16 @@ export default function Main (props) {
     const fileReader = new FileReader();
     fileReader.onloadend = e => {
       const content = bufferToHex(fileReader.result);
-      setProposal(`0x${content}`);
+      setCode(`0x${content}`);
     };
 
     fileReader.readAsArrayBuffer(file);
   };
 
+  // There is no sudo key, a council member proposes the upgrade and 3/4 of the council
+  //   must approve it.
+  const proposal = code && api.tx.parameters ? api.tx.parameters.setCode(code) : null;
+
   return (
     <Grid.Column width={8}>
       <h1>Upgrade Runtime</h1>
@@ -36,17 +43,25 @@ export default function Main (props) {
             onChange={e => handleFileChosen(e.target.files[0])}
           />
         </Form.Field>
+        <Form.Field>
+          <Input
+            type='number'
+            label='Council Threshold'
+            placeholder='Votes needed, at least 3/4 of the council'
+            onChange={(_, { value }) => setThreshold(value)}
+          />
+        </Form.Field>
         <Form.Field style={{ textAlign: 'center' }}>
           <TxButton
             accountPair={accountPair}
-            label='Upgrade'
-            type='UNCHECKED-SUDO-TX'
+            label='Propose Upgrade'
+            type='SIGNED-TX'
             setStatus={setStatus}
             attrs={{
-              palletRpc: 'system',
-              callable: 'setCode',
-              inputParams: [proposal],
-              paramFields: [true]
+              palletRpc: 'council',
+              callable: 'propose',
+              inputParams: [threshold, proposal, proposal ? proposal.encodedLength : null],
+              paramFields: [true, true, true]
             }}
           />
         </Form.Field>
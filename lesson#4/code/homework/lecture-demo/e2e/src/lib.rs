@@ -0,0 +1,40 @@
+//! End-to-end harness for the price oracle.
+//!
+//! It starts a `MockPriceServer` answering like `api.coincap.io`, writes a dev chain spec whose
+//!   `pallet_dot_price` feeds point at that server, runs the `node-template` binary on it and
+//!   talks to the node over JSON-RPC. No network access is needed.
+//!
+//! The node binary is taken from `$NODE_BINARY`, or `target/release/node-template` of this
+//!   workspace. Build it first, then run the ignored tests:
+//!
+//! ```text
+//! cargo build --release -p node-template
+//! cargo test -p e2e -- --ignored --test-threads 1
+//! ```
+//!
+//! Set `$E2E_SEALING` to pass a `--sealing` mode to the node, e.g. `E2E_SEALING=500` seals a
+//!   block every half second instead of waiting for 6 second Aura slots.
+
+mod mock_server;
+mod node;
+mod rpc;
+
+pub use mock_server::MockPriceServer;
+pub use node::{Feed, TestNode};
+pub use rpc::RpcClient;
+
+use std::time::{Duration, Instant};
+
+/// Poll `f` every second until it returns `Some`, or give up after `timeout`.
+pub fn wait_for<T>(timeout: Duration, mut f: impl FnMut() -> Option<T>) -> Option<T> {
+	let start = Instant::now();
+	loop {
+		if let Some(v) = f() {
+			return Some(v);
+		}
+		if start.elapsed() > timeout {
+			return None;
+		}
+		std::thread::sleep(Duration::from_secs(1));
+	}
+}
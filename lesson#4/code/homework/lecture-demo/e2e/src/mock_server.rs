@@ -0,0 +1,157 @@
+//! A minimal HTTP/1.1 server standing in for the price APIs the offchain worker queries.
+
+use std::{
+	collections::HashMap,
+	io::{self, BufRead, BufReader, Write},
+	net::{SocketAddr, TcpListener, TcpStream},
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc, Mutex,
+	},
+	thread,
+	time::Duration,
+};
+
+/// What the server answers on one path.
+#[derive(Clone, Debug)]
+struct Route {
+	status: u16,
+	body: Vec<u8>,
+	delay: Duration,
+	hits: usize,
+}
+
+impl Default for Route {
+	fn default() -> Self {
+		Route { status: 200, body: Vec::new(), delay: Duration::from_secs(0), hits: 0 }
+	}
+}
+
+type Routes = Arc<Mutex<HashMap<String, Route>>>;
+
+/// Serves configurable prices, delays and failures on `127.0.0.1`. Unknown paths get a `404`.
+///   The server stops when dropped.
+pub struct MockPriceServer {
+	addr: SocketAddr,
+	routes: Routes,
+	shutdown: Arc<AtomicBool>,
+}
+
+impl MockPriceServer {
+	/// Bind to a free local port and serve requests from a background thread.
+	pub fn start() -> io::Result<Self> {
+		let listener = TcpListener::bind("127.0.0.1:0")?;
+		let addr = listener.local_addr()?;
+		let routes = Routes::default();
+		let shutdown = Arc::new(AtomicBool::new(false));
+
+		let (r, s) = (routes.clone(), shutdown.clone());
+		thread::spawn(move || {
+			for stream in listener.incoming() {
+				if s.load(Ordering::SeqCst) {
+					break;
+				}
+				if let Ok(stream) = stream {
+					let r = r.clone();
+					thread::spawn(move || {
+						let _ = handle(stream, &r);
+					});
+				}
+			}
+		});
+
+		Ok(MockPriceServer { addr, routes, shutdown })
+	}
+
+	/// The full URL of `path` on this server.
+	pub fn url(&self, path: &str) -> String {
+		format!("http://{}{}", self.addr, path)
+	}
+
+	/// Answer `path` with `200` and a coincap-like document whose `data.priceUsd` is `price`.
+	pub fn set_price(&self, path: &str, price: &str) {
+		let body = format!(r#"{{"data":{{"id":"polkadot","priceUsd":"{}"}}}}"#, price);
+		self.update(path, |route| {
+			route.status = 200;
+			route.body = body.into_bytes();
+		});
+	}
+
+	/// Answer `path` with `status` and an empty body.
+	pub fn set_status(&self, path: &str, status: u16) {
+		self.update(path, |route| {
+			route.status = status;
+			route.body = Vec::new();
+		});
+	}
+
+	/// Wait `delay` before answering `path`.
+	pub fn set_delay(&self, path: &str, delay: Duration) {
+		self.update(path, |route| route.delay = delay);
+	}
+
+	/// Number of requests received on `path` so far.
+	pub fn hits(&self, path: &str) -> usize {
+		self.routes.lock().unwrap().get(path).map_or(0, |route| route.hits)
+	}
+
+	fn update(&self, path: &str, f: impl FnOnce(&mut Route)) {
+		f(self.routes.lock().unwrap().entry(path.to_string()).or_default());
+	}
+}
+
+impl Drop for MockPriceServer {
+	fn drop(&mut self) {
+		self.shutdown.store(true, Ordering::SeqCst);
+		// Wake up the accept loop so it sees the flag.
+		let _ = TcpStream::connect(self.addr);
+	}
+}
+
+fn handle(stream: TcpStream, routes: &Routes) -> io::Result<()> {
+	let mut reader = BufReader::new(stream.try_clone()?);
+	let mut request_line = String::new();
+	reader.read_line(&mut request_line)?;
+	// Skip the headers, the offchain worker only sends GET requests without a body.
+	loop {
+		let mut line = String::new();
+		if reader.read_line(&mut line)? == 0 || line == "\r\n" {
+			break;
+		}
+	}
+
+	let path = request_line.split_whitespace().nth(1).unwrap_or("/").to_string();
+	let route = {
+		let mut routes = routes.lock().unwrap();
+		match routes.get_mut(&path) {
+			Some(route) => {
+				route.hits += 1;
+				route.clone()
+			}
+			None => Route { status: 404, ..Default::default() },
+		}
+	};
+
+	thread::sleep(route.delay);
+	respond(stream, route.status, &route.body)
+}
+
+fn respond(mut stream: TcpStream, status: u16, body: &[u8]) -> io::Result<()> {
+	let reason = match status {
+		200 => "OK",
+		404 => "Not Found",
+		429 => "Too Many Requests",
+		500 => "Internal Server Error",
+		503 => "Service Unavailable",
+		_ => "Unknown",
+	};
+	write!(
+		stream,
+		"HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+		status,
+		reason,
+		body.len()
+	)?;
+	stream.write_all(body)?;
+	stream.flush()
+}
@@ -0,0 +1,166 @@
+//! Spawning a dev node whose price feeds point at a local server.
+
+use crate::{wait_for, RpcClient};
+use codec::Encode;
+use serde_json::{json, Value};
+use sp_core::hashing::{blake2_128, twox_128};
+use std::{
+	collections::VecDeque,
+	env, fs,
+	net::{SocketAddr, TcpListener},
+	path::{Path, PathBuf},
+	process::{Child, Command, Stdio},
+	time::Duration,
+};
+
+/// Key type of the oracle keys, see `pallet_dot_price::KEY_TYPE`.
+pub const ORACLE_KEY_TYPE: &str = "demo";
+
+/// A price feed written into the `pallet_dot_price` genesis config.
+#[derive(Clone, Debug)]
+pub struct Feed {
+	pub symbol: String,
+	pub urls: Vec<String>,
+	pub json_path: String,
+	pub decimals: u8,
+	pub period: u32,
+}
+
+impl Feed {
+	/// A DOT feed reading `data.priceUsd` with 6 decimals every `period` blocks.
+	pub fn dot(urls: Vec<String>, period: u32) -> Self {
+		Feed {
+			symbol: "DOT".into(),
+			urls,
+			json_path: "data.priceUsd".into(),
+			decimals: 6,
+			period,
+		}
+	}
+
+	fn to_genesis(&self) -> Value {
+		let urls: Vec<&[u8]> = self.urls.iter().map(|url| url.as_bytes()).collect();
+		json!([
+			self.symbol.as_bytes(),
+			urls,
+			self.json_path.as_bytes(),
+			self.decimals,
+			self.period
+		])
+	}
+}
+
+/// A running `node-template --dev`-like node, killed when dropped.
+pub struct TestNode {
+	child: Child,
+	base_path: PathBuf,
+	pub rpc: RpcClient,
+}
+
+impl TestNode {
+	/// `$NODE_BINARY`, or the release build of this workspace.
+	pub fn binary() -> PathBuf {
+		env::var_os("NODE_BINARY").map(PathBuf::from).unwrap_or_else(|| {
+			Path::new(env!("CARGO_MANIFEST_DIR")).join("../target/release/node-template")
+		})
+	}
+
+	/// Start a node on the `dev` chain with `feeds` replacing the genesis price feeds. Alice is
+	///   the only validator there, so her submissions alone finalize price rounds.
+	pub fn spawn(name: &str, feeds: &[Feed]) -> Self {
+		let base_path = env::temp_dir().join(format!("e2e-{}-{}", name, std::process::id()));
+		let _ = fs::remove_dir_all(&base_path);
+		fs::create_dir_all(&base_path).expect("base path can be created");
+		let spec = write_chain_spec(&base_path, feeds);
+
+		let rpc_port = free_port();
+		let mut command = Command::new(Self::binary());
+		if let Some(sealing) = env::var_os("E2E_SEALING") {
+			command.arg("--sealing").arg(sealing);
+		}
+		let child = command
+			.arg("--chain").arg(&spec)
+			.arg("--base-path").arg(base_path.join("data"))
+			.arg("--alice")
+			.args(&["--port", &free_port().to_string()])
+			.args(&["--ws-port", &free_port().to_string()])
+			.args(&["--rpc-port", &rpc_port.to_string()])
+			.args(&["--rpc-methods", "Unsafe"])
+			.args(&["--offchain-worker", "Always"])
+			.args(&["--enable-offchain-indexing", "true"])
+			.arg("--no-prometheus")
+			.arg("--no-telemetry")
+			.stdout(Stdio::null())
+			.stderr(fs::File::create(base_path.join("node.log")).expect("log file can be created"))
+			.spawn()
+			.expect("node binary can be started, build it with `cargo build --release`");
+
+		let node = TestNode {
+			child,
+			base_path,
+			rpc: RpcClient::new(SocketAddr::from(([127, 0, 0, 1], rpc_port))),
+		};
+		wait_for(Duration::from_secs(60), || node.rpc.block_number().ok())
+			.expect("node RPC comes up within a minute");
+		node
+	}
+
+	/// The finalized prices of `symbol` kept on-chain, oldest first.
+	pub fn prices(&self, symbol: &str) -> Vec<u64> {
+		let encoded = symbol.as_bytes().to_vec().encode();
+		let key = [
+			&twox_128(b"Example")[..],
+			&twox_128(b"Prices")[..],
+			&blake2_128(&encoded)[..],
+			&encoded[..],
+		]
+		.concat();
+		self.rpc
+			.storage::<VecDeque<u64>>(&key)
+			.expect("storage can be queried")
+			.map(Vec::from)
+			.unwrap_or_default()
+	}
+
+	/// Wait until `price` is among the on-chain prices of `symbol`.
+	pub fn wait_for_price(&self, symbol: &str, price: u64, timeout: Duration) -> bool {
+		wait_for(timeout, || if self.prices(symbol).contains(&price) { Some(()) } else { None })
+			.is_some()
+	}
+
+	/// The node output, for test failure messages.
+	pub fn log(&self) -> String {
+		fs::read_to_string(self.base_path.join("node.log")).unwrap_or_default()
+	}
+}
+
+impl Drop for TestNode {
+	fn drop(&mut self) {
+		let _ = self.child.kill();
+		let _ = self.child.wait();
+		if !std::thread::panicking() {
+			let _ = fs::remove_dir_all(&self.base_path);
+		}
+	}
+}
+
+/// Export the `dev` chain spec and replace its price feeds.
+fn write_chain_spec(dir: &Path, feeds: &[Feed]) -> PathBuf {
+	let output = Command::new(TestNode::binary())
+		.args(&["build-spec", "--chain", "dev", "--disable-default-bootnode"])
+		.stderr(Stdio::null())
+		.output()
+		.expect("node binary can export the dev chain spec");
+	let mut spec: Value = serde_json::from_slice(&output.stdout).expect("chain spec is JSON");
+
+	spec["genesis"]["runtime"]["palletDotPrice"]["feeds"] =
+		Value::Array(feeds.iter().map(Feed::to_genesis).collect());
+
+	let path = dir.join("chain-spec.json");
+	fs::write(&path, serde_json::to_vec_pretty(&spec).unwrap()).expect("chain spec can be written");
+	path
+}
+
+fn free_port() -> u16 {
+	TcpListener::bind("127.0.0.1:0").and_then(|l| l.local_addr()).map(|a| a.port()).unwrap()
+}
@@ -0,0 +1,74 @@
+//! A blocking JSON-RPC client over plain HTTP, enough for the few calls the tests make.
+
+use codec::Decode;
+use serde_json::{json, Value};
+use std::{
+	io::{Read, Write},
+	net::{SocketAddr, TcpStream},
+	time::Duration,
+};
+
+pub struct RpcClient {
+	addr: SocketAddr,
+}
+
+impl RpcClient {
+	pub fn new(addr: SocketAddr) -> Self {
+		RpcClient { addr }
+	}
+
+	/// Call `method` and return its `result`, or the `error` object as a string.
+	pub fn call(&self, method: &str, params: Value) -> Result<Value, String> {
+		let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }).to_string();
+
+		let mut stream = TcpStream::connect_timeout(&self.addr, Duration::from_secs(5))
+			.map_err(|e| e.to_string())?;
+		stream.set_read_timeout(Some(Duration::from_secs(30))).map_err(|e| e.to_string())?;
+		write!(
+			stream,
+			"POST / HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+			self.addr,
+			body.len(),
+			body
+		)
+		.map_err(|e| e.to_string())?;
+
+		let mut response = String::new();
+		stream.read_to_string(&mut response).map_err(|e| e.to_string())?;
+		let json = response.splitn(2, "\r\n\r\n").nth(1).ok_or("malformed http response")?;
+		let mut reply: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+
+		match reply.get("error") {
+			Some(error) => Err(error.to_string()),
+			None => Ok(reply["result"].take()),
+		}
+	}
+
+	/// The number of the best block.
+	pub fn block_number(&self) -> Result<u32, String> {
+		let header = self.call("chain_getHeader", json!([]))?;
+		let number = header["number"].as_str().ok_or("header without number")?;
+		u32::from_str_radix(number.trim_start_matches("0x"), 16).map_err(|e| e.to_string())
+	}
+
+	/// Read and decode the storage value at `key`, `None` if it is empty.
+	pub fn storage<T: Decode>(&self, key: &[u8]) -> Result<Option<T>, String> {
+		let value = self.call("state_getStorage", json!([format!("0x{}", hex::encode(key))]))?;
+		match value.as_str() {
+			Some(hex_value) => {
+				let bytes = hex::decode(hex_value.trim_start_matches("0x")).map_err(|e| e.to_string())?;
+				T::decode(&mut &bytes[..]).map(Some).map_err(|e| e.to_string())
+			}
+			None => Ok(None),
+		}
+	}
+
+	/// Add a key to the node keystore. Needs `--rpc-methods Unsafe`.
+	pub fn insert_key(&self, key_type: &str, suri: &str, public: &[u8]) -> Result<(), String> {
+		self.call(
+			"author_insertKey",
+			json!([key_type, suri, format!("0x{}", hex::encode(public))]),
+		)
+		.map(|_| ())
+	}
+}
@@ -0,0 +1,70 @@
+//! These tests start a real node, so they are ignored by default. See the crate docs for how
+//!   to run them.
+
+use e2e::{Feed, MockPriceServer, TestNode};
+use std::time::Duration;
+
+const PRIMARY: &str = "/v2/assets/polkadot";
+const FALLBACK: &str = "/mirror/v2/assets/polkadot";
+const TIMEOUT: Duration = Duration::from_secs(90);
+
+#[test]
+#[ignore]
+fn fetched_price_lands_on_chain() {
+	let server = MockPriceServer::start().unwrap();
+	server.set_price(PRIMARY, "5.2193847362");
+
+	let node = TestNode::spawn("price", &[Feed::dot(vec![server.url(PRIMARY)], 2)]);
+	assert!(node.wait_for_price("DOT", 5_219_384, TIMEOUT), "{}", node.log());
+
+	// A new price on the server is picked up by a later round.
+	server.set_price(PRIMARY, "6.01");
+	assert!(node.wait_for_price("DOT", 6_010_000, TIMEOUT), "{}", node.log());
+}
+
+#[test]
+#[ignore]
+fn failing_source_falls_back_to_the_next_url() {
+	let server = MockPriceServer::start().unwrap();
+	server.set_status(PRIMARY, 500);
+	server.set_price(FALLBACK, "7.5");
+
+	let node = TestNode::spawn(
+		"fallback",
+		&[Feed::dot(vec![server.url(PRIMARY), server.url(FALLBACK)], 2)],
+	);
+	assert!(node.wait_for_price("DOT", 7_500_000, TIMEOUT), "{}", node.log());
+	assert!(server.hits(PRIMARY) > 0);
+}
+
+#[test]
+#[ignore]
+fn slow_source_times_out_and_falls_back() {
+	let server = MockPriceServer::start().unwrap();
+	server.set_price(PRIMARY, "1.0");
+	// longer than the 3s fetch timeout of the offchain worker
+	server.set_delay(PRIMARY, Duration::from_secs(5));
+	server.set_price(FALLBACK, "8.25");
+
+	let node = TestNode::spawn(
+		"timeout",
+		&[Feed::dot(vec![server.url(PRIMARY), server.url(FALLBACK)], 2)],
+	);
+	assert!(node.wait_for_price("DOT", 8_250_000, TIMEOUT), "{}", node.log());
+	assert!(!node.prices("DOT").contains(&1_000_000));
+}
+
+#[test]
+#[ignore]
+fn unreachable_sources_submit_nothing() {
+	let server = MockPriceServer::start().unwrap();
+	server.set_status(PRIMARY, 503);
+
+	let node = TestNode::spawn("unreachable", &[Feed::dot(vec![server.url(PRIMARY)], 2)]);
+	let start = node.rpc.block_number().unwrap();
+	e2e::wait_for(TIMEOUT, || node.rpc.block_number().ok().filter(|n| *n >= start + 6))
+		.expect("blocks are produced");
+
+	assert!(node.prices("DOT").is_empty());
+	assert!(server.hits(PRIMARY) > 0);
+}
@@ -1,7 +1,9 @@
 use sp_core::{Pair, Public, sr25519};
 use node_template_runtime::{
-	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig,
-	SudoConfig, SystemConfig, WASM_BINARY, Signature
+	AccountId, AuraConfig, BalancesConfig, CouncilConfig, CouncilMembershipConfig, DotPriceConfig,
+	GenesisConfig, GrandpaConfig, IndicesConfig, SessionConfig, SudoConfig, SystemConfig, WASM_BINARY,
+	Signature, opaque::SessionKeys,
+	pallet_ocw_demo::crypto::Public as OcwId,
 };
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_finality_grandpa::AuthorityId as GrandpaId;
@@ -30,14 +32,20 @@ pub fn get_account_id_from_seed<TPublic: Public>(seed: &str) -> AccountId where
 	AccountPublic::from(get_from_seed::<TPublic>(seed)).into_account()
 }
 
-/// Generate an Aura authority key.
-pub fn authority_keys_from_seed(s: &str) -> (AuraId, GrandpaId) {
+/// Generate the validator account and the session keys of an authority.
+pub fn authority_keys_from_seed(s: &str) -> (AccountId, AuraId, GrandpaId, OcwId) {
 	(
+		get_account_id_from_seed::<sr25519::Public>(s),
 		get_from_seed::<AuraId>(s),
 		get_from_seed::<GrandpaId>(s),
+		get_from_seed::<OcwId>(s),
 	)
 }
 
+fn session_keys(aura: AuraId, grandpa: GrandpaId, demo: OcwId) -> SessionKeys {
+	SessionKeys { aura, grandpa, demo }
+}
+
 pub fn development_config() -> Result<ChainSpec, String> {
 	let wasm_binary = WASM_BINARY.ok_or("Development wasm binary not available".to_string())?;
 
@@ -62,6 +70,16 @@ pub fn development_config() -> Result<ChainSpec, String> {
 				get_account_id_from_seed::<sr25519::Public>("Alice//stash"),
 				get_account_id_from_seed::<sr25519::Public>("Bob//stash"),
 			],
+			// Price oracles
+			vec![
+				get_account_id_from_seed::<sr25519::Public>("Alice"),
+				get_account_id_from_seed::<sr25519::Public>("Bob"),
+			],
+			// Council members, they manage the price feeds and oracles
+			vec![
+				get_account_id_from_seed::<sr25519::Public>("Alice"),
+				get_account_id_from_seed::<sr25519::Public>("Bob"),
+			],
 			true,
 		),
 		// Bootnodes
@@ -110,6 +128,18 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 				get_account_id_from_seed::<sr25519::Public>("Eve//stash"),
 				get_account_id_from_seed::<sr25519::Public>("Ferdie//stash"),
 			],
+			// Price oracles
+			vec![
+				get_account_id_from_seed::<sr25519::Public>("Alice"),
+				get_account_id_from_seed::<sr25519::Public>("Bob"),
+				get_account_id_from_seed::<sr25519::Public>("Charlie"),
+			],
+			// Council members, they manage the price feeds and oracles
+			vec![
+				get_account_id_from_seed::<sr25519::Public>("Alice"),
+				get_account_id_from_seed::<sr25519::Public>("Bob"),
+				get_account_id_from_seed::<sr25519::Public>("Charlie"),
+			],
 			true,
 		),
 		// Bootnodes
@@ -128,9 +158,11 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 /// Configure initial storage state for FRAME modules.
 fn testnet_genesis(
 	wasm_binary: &[u8],
-	initial_authorities: Vec<(AuraId, GrandpaId)>,
+	initial_authorities: Vec<(AccountId, AuraId, GrandpaId, OcwId)>,
 	root_key: AccountId,
 	endowed_accounts: Vec<AccountId>,
+	oracles: Vec<AccountId>,
+	council: Vec<AccountId>,
 	_enable_println: bool,
 ) -> GenesisConfig {
 	GenesisConfig {
@@ -143,15 +175,41 @@ fn testnet_genesis(
 			// Configure endowed accounts with initial balance of 1 << 60.
 			balances: endowed_accounts.iter().cloned().map(|k|(k, 1 << 60)).collect(),
 		}),
+		pallet_indices: Some(IndicesConfig {
+			indices: vec![],
+		}),
+		// The Aura and Grandpa authorities are set from the session keys.
 		pallet_aura: Some(AuraConfig {
-			authorities: initial_authorities.iter().map(|x| (x.0.clone())).collect(),
+			authorities: vec![],
 		}),
 		pallet_grandpa: Some(GrandpaConfig {
-			authorities: initial_authorities.iter().map(|x| (x.1.clone(), 1)).collect(),
+			authorities: vec![],
+		}),
+		pallet_session: Some(SessionConfig {
+			keys: initial_authorities.iter().map(|x| {
+				(x.0.clone(), x.0.clone(), session_keys(x.1.clone(), x.2.clone(), x.3.clone()))
+			}).collect(),
 		}),
 		pallet_sudo: Some(SudoConfig {
 			// Assign network admin rights.
 			key: root_key,
 		}),
+		// Members are set by the membership pallet.
+		pallet_collective_Instance1: Some(CouncilConfig::default()),
+		pallet_membership_Instance1: Some(CouncilMembershipConfig {
+			members: council,
+			phantom: Default::default(),
+		}),
+		pallet_dot_price: Some(DotPriceConfig {
+			oracles,
+			// (symbol, urls, json path, decimals, period in blocks)
+			feeds: vec![(
+				b"DOT".to_vec(),
+				vec![b"https://api.coincap.io/v2/assets/polkadot".to_vec()],
+				b"data.priceUsd".to_vec(),
+				6,
+				10,
+			)],
+		}),
 	}
 }
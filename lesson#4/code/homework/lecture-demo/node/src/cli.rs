@@ -1,5 +1,5 @@
 use structopt::StructOpt;
-use sc_cli::RunCmd;
+use sc_cli::{KeystoreParams, RunCmd, SharedParams};
 
 #[derive(Debug, StructOpt)]
 pub struct Cli {
@@ -8,10 +8,18 @@ pub struct Cli {
 
 	#[structopt(flatten)]
 	pub run: RunCmd,
+
+	/// How blocks are produced: `aura`, `instant` (a block per transaction), `manual` (a block
+	/// per `engine_createBlock` call) or a sealing interval in milliseconds.
+	#[structopt(long, default_value = "aura")]
+	pub sealing: crate::service::Sealing,
 }
 
 #[derive(Debug, StructOpt)]
 pub enum Subcommand {
+	/// Key management cli utilities
+	Key(KeySubcommand),
+
 	/// Build a chain specification.
 	BuildSpec(sc_cli::BuildSpecCmd),
 
@@ -37,3 +45,28 @@ pub enum Subcommand {
 	#[structopt(name = "benchmark", about = "Benchmark runtime pallets.")]
 	Benchmark(frame_benchmarking_cli::BenchmarkCmd),
 }
+
+#[derive(Debug, StructOpt)]
+pub enum KeySubcommand {
+	/// Insert an offchain worker signing key into the node keystore.
+	InsertOcw(InsertOcwCmd),
+
+	#[structopt(flatten)]
+	Base(sc_cli::KeySubcommand),
+}
+
+/// The `key insert-ocw` command.
+#[derive(Debug, StructOpt)]
+pub struct InsertOcwCmd {
+	/// The secret key URI, e.g. `//Alice` or a mnemonic phrase.
+	#[structopt(long)]
+	pub suri: String,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub keystore_params: KeystoreParams,
+}
@@ -16,10 +16,10 @@
 // limitations under the License.
 
 use crate::{chain_spec, service};
-use crate::cli::{Cli, Subcommand};
-use sc_cli::{SubstrateCli, RuntimeVersion, Role, ChainSpec};
-use sc_service::PartialComponents;
-use node_template_runtime::Block;
+use crate::cli::{Cli, InsertOcwCmd, KeySubcommand, Subcommand};
+use sc_cli::{CliConfiguration, KeystoreParams, SharedParams, SubstrateCli, RuntimeVersion, Role, ChainSpec};
+use sc_service::{config::KeystoreConfig, Configuration, PartialComponents};
+use node_template_runtime::{self as runtime, Block};
 
 impl SubstrateCli for Cli {
 	fn impl_name() -> String {
@@ -66,6 +66,11 @@ pub fn run() -> sc_cli::Result<()> {
 	let cli = Cli::from_args();
 
 	match &cli.subcommand {
+		Some(Subcommand::Key(KeySubcommand::InsertOcw(cmd))) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| cmd.run(config))
+		},
+		Some(Subcommand::Key(KeySubcommand::Base(cmd))) => cmd.run(),
 		Some(Subcommand::BuildSpec(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.sync_run(|config| cmd.run(config.chain_spec, config.network))
@@ -126,10 +131,40 @@ pub fn run() -> sc_cli::Result<()> {
 		},
 		None => {
 			let runner = cli.create_runner(&cli.run)?;
+			let sealing = cli.sealing;
 			runner.run_node_until_exit(|config| match config.role {
 				Role::Light => service::new_light(config),
-				_ => service::new_full(config),
+				_ => service::new_full(config, sealing),
 			})
 		}
 	}
 }
+
+impl InsertOcwCmd {
+	/// Store the sr25519 `KEY_TYPE` key of `suri` in the keystore of the selected chain.
+	pub fn run(&self, config: Configuration) -> sc_cli::Result<()> {
+		let (path, password) = match config.keystore {
+			KeystoreConfig::Path { path, password } => (path, password),
+			KeystoreConfig::InMemory => return Err("The keystore is in memory, nothing to insert into.".into()),
+		};
+		let keystore = sc_keystore::Store::open(path, password)
+			.map_err(|e| format!("Opening the keystore failed: {:?}", e))?;
+		let pair = keystore.write()
+			.insert_by_type::<runtime::pallet_ocw_demo::crypto::Pair>(runtime::pallet_ocw_demo::KEY_TYPE, &self.suri)
+			.map_err(|e| format!("Inserting the key failed: {:?}", e))?;
+
+		let public: sp_core::sr25519::Public = sp_core::Pair::public(&pair).into();
+		println!("Inserted OCW key {}", sp_core::crypto::Ss58Codec::to_ss58check(&public));
+		Ok(())
+	}
+}
+
+impl CliConfiguration for InsertOcwCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn keystore_params(&self) -> Option<&KeystoreParams> {
+		Some(&self.keystore_params)
+	}
+}
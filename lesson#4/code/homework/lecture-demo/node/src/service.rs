@@ -1,11 +1,13 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::{Arc, atomic::{AtomicU64, Ordering}};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::str::FromStr;
+use futures::{StreamExt, channel::mpsc};
 use sc_client_api::{ExecutorProvider, RemoteBackend};
 use node_template_runtime::{self as runtime, opaque::Block, RuntimeApi};
 use sc_service::{error::Error as ServiceError, Configuration, TaskManager};
-use sp_inherents::InherentDataProviders;
+use sp_inherents::{InherentData, InherentDataProviders, InherentIdentifier, ProvideInherentData};
 use sc_executor::native_executor_instance;
 pub use sc_executor::NativeExecutor;
 use sp_consensus_aura::sr25519::{AuthorityPair as AuraPair};
@@ -23,6 +25,91 @@ type FullClient = sc_service::TFullClient<Block, RuntimeApi, Executor>;
 type FullBackend = sc_service::TFullBackend<Block>;
 type FullSelectChain = sc_consensus::LongestChain<FullBackend, Block>;
 
+/// How a full node produces blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sealing {
+	/// Aura slots finalized by Grandpa.
+	Aura,
+	/// A block as soon as a transaction enters the pool.
+	Instant,
+	/// A block whenever `engine_createBlock` is called.
+	Manual,
+	/// A block every given number of milliseconds, and on `engine_createBlock`.
+	Interval(u64),
+}
+
+impl FromStr for Sealing {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Ok(match s {
+			"aura" => Sealing::Aura,
+			"instant" => Sealing::Instant,
+			"manual" => Sealing::Manual,
+			ms => match ms.parse::<u64>() {
+				Ok(ms) if ms > 0 => Sealing::Interval(ms),
+				_ => return Err(format!(
+					"`{}` is not a sealing mode, use `aura`, `instant`, `manual` or milliseconds", s
+				)),
+			},
+		})
+	}
+}
+
+/// Provides the timestamp inherent of manually sealed blocks. Each block is one Aura slot after
+///   the previous one, so the runtime's slot and minimum period checks pass however fast blocks
+///   are sealed.
+struct SlotTimestampProvider {
+	last: AtomicU64,
+}
+
+impl SlotTimestampProvider {
+	/// Start from the current time.
+	fn new() -> Self {
+		let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+		SlotTimestampProvider { last: AtomicU64::new(now.as_millis() as u64) }
+	}
+}
+
+impl ProvideInherentData for SlotTimestampProvider {
+	fn inherent_identifier(&self) -> &'static InherentIdentifier {
+		&sp_timestamp::INHERENT_IDENTIFIER
+	}
+
+	fn provide_inherent_data(&self, inherent_data: &mut InherentData) -> Result<(), sp_inherents::Error> {
+		let timestamp = self.last.fetch_add(runtime::SLOT_DURATION, Ordering::SeqCst) + runtime::SLOT_DURATION;
+		inherent_data.put_data(sp_timestamp::INHERENT_IDENTIFIER, &timestamp)
+	}
+
+	fn error_to_string(&self, error: &[u8]) -> Option<String> {
+		Some(String::from_utf8_lossy(error).into())
+	}
+}
+
+/// Well-known accounts whose offchain worker keys are put in the keystore of `dev` nodes.
+pub const DEV_OCW_SEEDS: &[&str] = &["//Alice", "//Bob", "//Charlie", "//Dave", "//Eve", "//Ferdie"];
+
+/// Insert `KEY_TYPE` keys when running one of the development chains, so the signed offchain
+///   worker paths work without calling `author_insertKey`. A `dev` node is the only node of its
+///   chain and gets all `DEV_OCW_SEEDS`. A `local_testnet` node only gets the key of its own
+///   `--alice`, `--bob`, ... account, otherwise every node would submit for every oracle; nodes
+///   without one use `key insert-ocw`. The keys are ephemeral and never written to disk.
+fn insert_dev_ocw_keys(config: &Configuration, keystore: &sc_keystore::KeyStorePtr) -> Result<(), ServiceError> {
+	let seeds = match (config.chain_spec.id(), &config.dev_key_seed) {
+		("dev", _) => DEV_OCW_SEEDS.to_vec(),
+		("local_testnet", Some(seed)) => vec![seed.as_str()],
+		_ => return Ok(()),
+	};
+
+	let mut keystore = keystore.write();
+	for seed in seeds {
+		keystore.insert_ephemeral_from_seed_by_type::<runtime::pallet_ocw_demo::crypto::Pair>(
+			seed, runtime::pallet_ocw_demo::KEY_TYPE
+		).map_err(|e| ServiceError::Other(format!("Creating the OCW key of {} failed: {:?}", seed, e)))?;
+	}
+	Ok(())
+}
+
 pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponents<
 	FullClient, FullBackend, FullSelectChain,
 	sp_consensus::DefaultImportQueue<Block, FullClient>,
@@ -43,9 +130,7 @@ pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponen
 		sc_service::new_full_parts::<Block, RuntimeApi, Executor>(&config)?;
 	let client = Arc::new(client);
 
-	keystore.write().insert_ephemeral_from_seed_by_type::<runtime::pallet_ocw_demo::crypto::Pair>(
-		"//Alice", runtime::pallet_ocw_demo::KEY_TYPE
-	).expect("Creating key with account Alice should succeed.");
+	insert_dev_ocw_keys(config, &keystore)?;
 
 	let select_chain = sc_consensus::LongestChain::new(backend.clone());
 
@@ -84,7 +169,11 @@ pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponen
 }
 
 /// Builds a new service for a full client.
-pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
+pub fn new_full(config: Configuration, sealing: Sealing) -> Result<TaskManager, ServiceError> {
+	if sealing != Sealing::Aura {
+		return new_full_manual_seal(config, sealing);
+	}
+
 	let sc_service::PartialComponents {
 		client, backend, mut task_manager, import_queue, keystore, select_chain, transaction_pool,
 		inherent_data_providers,
@@ -124,11 +213,13 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
 		let client = client.clone();
 		let pool = transaction_pool.clone();
 
-		Box::new(move |deny_unsafe, _| {
+		Box::new(move |deny_unsafe, subscription_executor| {
 			let deps = crate::rpc::FullDeps {
 				client: client.clone(),
 				pool: pool.clone(),
 				deny_unsafe,
+				subscription_executor,
+				command_sink: None,
 			};
 
 			crate::rpc::create_full(deps)
@@ -230,6 +321,147 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
 	Ok(task_manager)
 }
 
+/// Builds a full node sealing blocks with manual seal instead of Aura and Grandpa, for
+///   development and tests. Instant and interval blocks are finalized as soon as they are sealed,
+///   so finality subscriptions keep working. `engine_createBlock` finalizes when asked to.
+pub fn new_full_manual_seal(config: Configuration, sealing: Sealing) -> Result<TaskManager, ServiceError> {
+	let (client, backend, keystore, mut task_manager) =
+		sc_service::new_full_parts::<Block, RuntimeApi, Executor>(&config)?;
+	let client = Arc::new(client);
+
+	insert_dev_ocw_keys(&config, &keystore)?;
+
+	let select_chain = sc_consensus::LongestChain::new(backend.clone());
+
+	let transaction_pool = sc_transaction_pool::BasicPool::new_full(
+		config.transaction_pool.clone(),
+		config.prometheus_registry(),
+		task_manager.spawn_handle(),
+		client.clone(),
+	);
+
+	let import_queue = sc_consensus_manual_seal::import_queue(
+		Box::new(client.clone()),
+		&task_manager.spawn_handle(),
+		config.prometheus_registry(),
+	);
+
+	let inherent_data_providers = InherentDataProviders::new();
+	inherent_data_providers
+		.register_provider(SlotTimestampProvider::new())
+		.map_err(|e| ServiceError::Other(format!("{:?}", e)))?;
+
+	let (network, network_status_sinks, system_rpc_tx, network_starter) =
+		sc_service::build_network(sc_service::BuildNetworkParams {
+			config: &config,
+			client: client.clone(),
+			transaction_pool: transaction_pool.clone(),
+			spawn_handle: task_manager.spawn_handle(),
+			import_queue,
+			on_demand: None,
+			block_announce_validator_builder: None,
+			finality_proof_request_builder: None,
+			finality_proof_provider: None,
+		})?;
+
+	if config.offchain_worker.enabled {
+		sc_service::build_offchain_workers(
+			&config, backend.clone(), task_manager.spawn_handle(), client.clone(), network.clone(),
+		);
+	}
+
+	let prometheus_registry = config.prometheus_registry().cloned();
+	let (command_sink, commands_stream) = mpsc::channel(1024);
+
+	let rpc_extensions_builder = {
+		let client = client.clone();
+		let pool = transaction_pool.clone();
+
+		Box::new(move |deny_unsafe, subscription_executor| {
+			let deps = crate::rpc::FullDeps {
+				client: client.clone(),
+				pool: pool.clone(),
+				deny_unsafe,
+				subscription_executor,
+				command_sink: Some(command_sink.clone()),
+			};
+
+			crate::rpc::create_full(deps)
+		})
+	};
+
+	sc_service::spawn_tasks(sc_service::SpawnTasksParams {
+		network,
+		client: client.clone(),
+		keystore,
+		task_manager: &mut task_manager,
+		transaction_pool: transaction_pool.clone(),
+		telemetry_connection_sinks: sc_service::TelemetryConnectionSinks::default(),
+		rpc_extensions_builder,
+		on_demand: None,
+		remote_blockchain: None,
+		backend, network_status_sinks, system_rpc_tx, config,
+	})?;
+
+	let proposer = sc_basic_authorship::ProposerFactory::new(
+		client.clone(),
+		transaction_pool.clone(),
+		prometheus_registry.as_ref(),
+	);
+
+	// `run_instant_seal` never finalizes, so instant sealing is driven through manual seal too.
+	let commands_stream = match sealing {
+		Sealing::Instant => transaction_pool.pool().validated_pool().import_notification_stream()
+			.map(|_| finalizing_seal_command(false))
+			.boxed(),
+		Sealing::Manual => commands_stream.boxed(),
+		Sealing::Interval(ms) => futures::stream::select(
+			commands_stream,
+			interval_commands(Duration::from_millis(ms)),
+		).boxed(),
+		Sealing::Aura => unreachable!("Aura is sealed by `new_full`"),
+	};
+	let authorship_future = sc_consensus_manual_seal::run_manual_seal(
+		sc_consensus_manual_seal::ManualSealParams {
+			block_import: client.clone(),
+			env: proposer,
+			client,
+			pool: transaction_pool.pool().clone(),
+			commands_stream,
+			select_chain,
+			consensus_data_provider: None,
+			inherent_data_providers,
+		},
+	);
+
+	// the sealing task is considered essential, i.e. if it
+	// fails we take down the service with it.
+	task_manager.spawn_essential_handle().spawn_blocking("manual-seal", authorship_future);
+
+	network_starter.start_network();
+	Ok(task_manager)
+}
+
+/// Seals a block on top of the best block and finalizes it right away.
+fn finalizing_seal_command(create_empty: bool) -> sc_consensus_manual_seal::EngineCommand<runtime::Hash> {
+	sc_consensus_manual_seal::EngineCommand::SealNewBlock {
+		create_empty,
+		finalize: true,
+		parent_hash: None,
+		sender: None,
+	}
+}
+
+/// An endless stream of commands sealing and finalizing a block every `period`.
+fn interval_commands(
+	period: Duration,
+) -> impl futures::Stream<Item = sc_consensus_manual_seal::EngineCommand<runtime::Hash>> {
+	futures::stream::unfold((), move |_| async move {
+		futures_timer::Delay::new(period).await;
+		Some((finalizing_seal_command(true), ()))
+	})
+}
+
 /// Builds a new service for a light client.
 pub fn new_light(config: Configuration) -> Result<TaskManager, ServiceError> {
 	let (client, backend, keystore, mut task_manager, on_demand) =
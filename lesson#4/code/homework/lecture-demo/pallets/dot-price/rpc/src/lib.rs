@@ -0,0 +1,172 @@
+//! RPC methods and subscriptions for the finalized prices of pallet-dot-price, backed by the
+//! `DotPriceApi` runtime API. Symbols are plain strings such as `"DOT"`.
+
+use std::sync::Arc;
+
+use futures::{future, StreamExt, TryStreamExt};
+use futures01::{Future, Sink};
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use jsonrpc_pubsub::{manager::SubscriptionManager, typed::Subscriber, SubscriptionId};
+use sc_client_api::BlockchainEvents;
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+pub use pallet_dot_price_runtime_api::{DotPriceApi as DotPriceRuntimeApi, FinalizedPrice, Price};
+
+/// A finalized price as sent to clients.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceUpdate {
+	pub symbol: String,
+	/// The round the price was finalized in.
+	pub round: u32,
+	pub price: Price,
+	/// `price / 10^decimals` is the price in USD.
+	pub decimals: u8,
+}
+
+impl PriceUpdate {
+	fn new(symbol: &str, finalized: FinalizedPrice) -> Self {
+		PriceUpdate {
+			symbol: symbol.into(),
+			round: finalized.round,
+			price: finalized.price,
+			decimals: finalized.decimals,
+		}
+	}
+}
+
+#[rpc]
+pub trait PriceApi<BlockHash> {
+	/// RPC metadata
+	type Metadata;
+
+	/// The last finalized price of `symbol`, `null` before its first round is finalized.
+	#[rpc(name = "price_latest")]
+	fn latest(&self, symbol: String, at: Option<BlockHash>) -> Result<Option<PriceUpdate>>;
+
+	/// Up to the `n` most recent finalized prices of `symbol`, oldest first.
+	#[rpc(name = "price_history")]
+	fn history(&self, symbol: String, n: u32, at: Option<BlockHash>) -> Result<Vec<Price>>;
+
+	/// Subscribe to the finalized prices of `symbol`. The price in the last finalized block, if
+	/// any, is sent right away, then the latest price of every newly finalized block, so prices
+	/// from blocks that get reorged away are never sent.
+	#[pubsub(subscription = "price_newPrice", subscribe, name = "price_subscribe")]
+	fn subscribe(&self, metadata: Self::Metadata, subscriber: Subscriber<PriceUpdate>, symbol: String);
+
+	/// Unsubscribe from price notifications.
+	#[pubsub(subscription = "price_newPrice", unsubscribe, name = "price_unsubscribe")]
+	fn unsubscribe(&self, metadata: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool>;
+}
+
+/// Serves `PriceApi` through the `DotPriceApi` runtime API.
+pub struct DotPrice<C, B> {
+	client: Arc<C>,
+	subscriptions: SubscriptionManager,
+	_marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> DotPrice<C, B> {
+	pub fn new(client: Arc<C>, executor: sc_rpc::SubscriptionTaskExecutor) -> Self {
+		DotPrice {
+			client,
+			subscriptions: SubscriptionManager::new(Arc::new(executor)),
+			_marker: Default::default(),
+		}
+	}
+}
+
+fn runtime_error(e: impl std::fmt::Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(1),
+		message: "Unable to query prices.".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+impl<C, Block> DotPrice<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: DotPriceRuntimeApi<Block>,
+{
+	fn latest_at(&self, symbol: &str, at: BlockId<Block>) -> Result<Option<PriceUpdate>> {
+		let finalized = self.client.runtime_api()
+			.latest_price(&at, symbol.as_bytes().to_vec())
+			.map_err(runtime_error)?;
+		Ok(finalized.map(|f| PriceUpdate::new(symbol, f)))
+	}
+
+	fn at(&self, at: Option<<Block as BlockT>::Hash>) -> BlockId<Block> {
+		BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash))
+	}
+}
+
+impl<C, Block> PriceApi<<Block as BlockT>::Hash> for DotPrice<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + BlockchainEvents<Block> + Send + Sync + 'static,
+	C::Api: DotPriceRuntimeApi<Block>,
+{
+	type Metadata = sc_rpc::Metadata;
+
+	fn latest(&self, symbol: String, at: Option<<Block as BlockT>::Hash>) -> Result<Option<PriceUpdate>> {
+		self.latest_at(&symbol, self.at(at))
+	}
+
+	fn history(&self, symbol: String, n: u32, at: Option<<Block as BlockT>::Hash>) -> Result<Vec<Price>> {
+		self.client.runtime_api()
+			.price_history(&self.at(at), symbol.into_bytes(), n)
+			.map_err(runtime_error)
+	}
+
+	fn subscribe(&self, _metadata: Self::Metadata, subscriber: Subscriber<PriceUpdate>, symbol: String) {
+		let current = match self.latest_at(&symbol, BlockId::hash(self.client.info().finalized_hash)) {
+			Ok(current) => current,
+			Err(e) => {
+				let _ = subscriber.reject(e);
+				return;
+			},
+		};
+
+		// A round is finalized at most once, so a new round means a new price. Finality may jump
+		// several blocks at once, only the latest price of the finalized head is sent then.
+		let mut last_round = current.as_ref().map(|update| update.round);
+		let client = self.client.clone();
+		let updates = self.client.finality_notification_stream()
+			.filter_map(move |notification| {
+				let update = client.runtime_api()
+					.latest_price(&BlockId::hash(notification.hash), symbol.as_bytes().to_vec())
+					.ok()
+					.flatten()
+					.filter(|finalized| Some(finalized.round) != last_round)
+					.map(|finalized| PriceUpdate::new(&symbol, finalized));
+				if let Some(ref update) = update {
+					last_round = Some(update.round);
+				}
+				future::ready(update)
+			});
+
+		let stream = futures::stream::iter(current)
+			.chain(updates)
+			.map(|update| Ok::<_, ()>(Ok(update)))
+			.boxed()
+			.compat();
+
+		self.subscriptions.add(subscriber, |sink| {
+			sink
+				.sink_map_err(|_| ())
+				.send_all(stream)
+				// the stream only ends when the node shuts down or the client unsubscribes
+				.map(|_| ())
+		});
+	}
+
+	fn unsubscribe(&self, _metadata: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool> {
+		Ok(self.subscriptions.cancel(id))
+	}
+}
@@ -0,0 +1,16 @@
+//! Runtime API definition for pallet-dot-price.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::vec::Vec;
+
+pub use pallet_dot_price::{FinalizedPrice, Price, Symbol};
+
+sp_api::decl_runtime_apis! {
+	pub trait DotPriceApi {
+		/// The last finalized price of `symbol`.
+		fn latest_price(symbol: Symbol) -> Option<FinalizedPrice>;
+		/// Up to the `n` most recent finalized prices of `symbol`, oldest first.
+		fn price_history(symbol: Symbol, n: u32) -> Vec<Price>;
+	}
+}
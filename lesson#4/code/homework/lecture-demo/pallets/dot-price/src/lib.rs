@@ -5,34 +5,31 @@
 #[cfg(test)]
 mod tests;
 
-use core::{convert::TryInto, fmt};
 use frame_support::{
-    debug, decl_error, decl_event, decl_module, decl_storage, dispatch::DispatchResult,
+    debug, decl_error, decl_event, decl_module, decl_storage, dispatch::DispatchResult, ensure,
+    traits::{EnsureOrigin, Get}, weights::Weight,
 };
 use parity_scale_codec::{Decode, Encode};
 
 use frame_system::{
     self as system, ensure_none, ensure_signed,
     offchain::{
-        AppCrypto, CreateSignedTransaction, SendSignedTransaction, SendUnsignedTransaction,
-        SignedPayload, Signer, SigningTypes, SubmitTransaction,
+        AppCrypto, CreateSignedTransaction, SendUnsignedTransaction, SignedPayload, Signer,
+        SigningTypes,
     },
 };
 use sp_core::crypto::KeyTypeId;
 use sp_runtime::{
-    offchain as rt_offchain,
-    offchain::{
-        storage::StorageValueRef,
-        storage_lock::{BlockAndTime, StorageLock},
-    },
+    offchain::storage::StorageValueRef,
+    traits::{CheckedSub, IdentifyAccount, SaturatedConversion, Saturating, Zero},
     transaction_validity::{
         InvalidTransaction, TransactionSource, TransactionValidity, ValidTransaction,
     },
-    RuntimeDebug,
+    Permill, RuntimeDebug,
 };
-use sp_std::{collections::vec_deque::VecDeque, prelude::*, str};
+use sp_std::{collections::vec_deque::VecDeque, prelude::*, str, string::ToString};
 
-use serde::{Deserialize, Deserializer};
+use ocw_http::{HttpClient, HttpError};
 
 /// Defines application identifier for crypto keys of this module.
 ///
@@ -43,18 +40,26 @@ use serde::{Deserialize, Deserializer};
 /// The keys can be inserted manually via RPC (see `author_insertKey`).
 pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"demo");
 pub const NUM_VEC_LEN: usize = 10;
+/// Number of price observations kept per feed for TWAP computation.
+pub const MAX_OBSERVATIONS: usize = 64;
 /// The type to sign and send transactions.
 pub const UNSIGNED_TXS_PRIORITY: u64 = 100;
 
-// We are fetching information from the github public API about organization`substrate-developer-hub`.
-pub const HTTP_REMOTE_REQUEST: &str = "https://api.github.com/orgs/substrate-developer-hub";
 pub const HTTP_HEADER_USER_AGENT: &str = "jimmychu0807";
 
-pub const DOT_PRICE_HTTP_REMOTE_REQUEST: &str = "https://api.coincap.io/v2/assets/polkadot";
+/// Upper bound on `FeedConfig::decimals`, so that scaled prices fit in a `Price`.
+pub const MAX_FEED_DECIMALS: u8 = 12;
 
 pub const FETCH_TIMEOUT_PERIOD: u64 = 3000; // in milli-seconds
-pub const LOCK_TIMEOUT_EXPIRATION: u64 = FETCH_TIMEOUT_PERIOD + 1000; // in milli-seconds
-pub const LOCK_BLOCK_EXPIRATION: u32 = 3; // in block number
+
+/// A price in USD, scaled by `10^decimals` of the feed it belongs to.
+pub type Price = u64;
+
+/// The symbol of an asset with a price feed, e.g. `b"DOT"`.
+pub type Symbol = Vec<u8>;
+
+/// The index of a price submission round.
+pub type RoundIndex = u32;
 
 /// Based on the above `KeyTypeId` we need to generate a pallet-specific crypto type wrapper.
 /// We can utilize the supported crypto kinds (`sr25519`, `ed25519` and `ecdsa`) and augment
@@ -97,54 +102,164 @@ impl<T: SigningTypes> SignedPayload<T> for Payload<T::Public> {
     }
 }
 
-// ref: https://serde.rs/container-attrs.html#crate
-#[derive(Deserialize, Encode, Decode, Default)]
-struct GithubInfo {
-    // Specify our own deserializing function to convert JSON string to vector of bytes
-    #[serde(deserialize_with = "de_string_to_bytes")]
-    login: Vec<u8>,
-    #[serde(deserialize_with = "de_string_to_bytes")]
-    blog: Vec<u8>,
-    public_repos: u32,
+/// A price observed by an oracle for a given feed and round, signed with the oracle's key.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct PricePayload<Public> {
+    pub symbol: Symbol,
+    pub round: RoundIndex,
+    pub price: Price,
+    pub public: Public,
+}
+
+impl<T: SigningTypes> SignedPayload<T> for PricePayload<T::Public> {
+    fn public(&self) -> T::Public {
+        self.public.clone()
+    }
+}
+
+/// The most recently finalized price of a feed.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct FinalizedPrice {
+    /// The round the price was finalized in.
+    pub round: RoundIndex,
+    pub price: Price,
+    /// Decimals of the feed, `price / 10^decimals` is the price in USD.
+    pub decimals: u8,
+}
+
+/// Where and how often the offchain worker fetches the price of an asset.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct FeedConfig<BlockNumber> {
+    /// HTTP endpoints returning the same JSON document. They are tried in order, so later
+    ///   ones serve as fallbacks.
+    pub urls: Vec<Vec<u8>>,
+    /// Dot separated path to the price inside the JSON document, e.g. `b"data.priceUsd"`.
+    ///   Array elements are addressed by their index.
+    pub json_path: Vec<u8>,
+    /// Number of decimals kept when the fetched price is turned into a `Price`.
+    pub decimals: u8,
+    /// Number of blocks between two fetches of this feed.
+    pub period: BlockNumber,
+}
+
+/// A finalized price together with the running sum of `price * blocks` up to the block it was
+///   finalized in. The difference of two cumulative values divided by the blocks between them
+///   is the time-weighted average price over that span.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Observation<BlockNumber> {
+    pub block: BlockNumber,
+    pub price: Price,
+    pub cumulative: u128,
+}
+
+/// Read access to the price feeds for other pallets.
+pub trait PriceProvider<BlockNumber> {
+    /// The most recently finalized price of `symbol`.
+    fn latest(symbol: &[u8]) -> Option<Price>;
+    /// The time-weighted average price of `symbol` over the last `window` blocks, or `None`
+    ///   when the recorded history does not cover the whole window.
+    fn twap(symbol: &[u8], window: BlockNumber) -> Option<Price>;
+    /// Whether the last price of `symbol` is older than `max_age` blocks, or there is none.
+    fn is_stale(symbol: &[u8], max_age: BlockNumber) -> bool;
+}
+
+impl<BlockNumber> PriceProvider<BlockNumber> for () {
+    fn latest(_symbol: &[u8]) -> Option<Price> {
+        None
+    }
+    fn twap(_symbol: &[u8], _window: BlockNumber) -> Option<Price> {
+        None
+    }
+    fn is_stale(_symbol: &[u8], _max_age: BlockNumber) -> bool {
+        true
+    }
+}
+
+/// The offchain worker keys of the current session's validators, kept by the pallet that
+///   handles the `KEY_TYPE` session keys.
+pub trait SessionAuthorities<Public> {
+    fn session_authorities() -> Vec<Public>;
 }
 
-#[derive(Deserialize, Encode, Decode, Default, Clone)]
-struct DotPriceInfo {
-    #[serde(deserialize_with = "de_string_to_bytes")]
-    price_usd: Vec<u8>,
+/// Counters kept for an oracle that did not contribute a usable price to a round.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct OraclePenalty {
+    /// Rounds that were finalized without a submission from this oracle.
+    pub missed: u32,
+    /// Rounds where this oracle's price was rejected as an outlier.
+    pub deviated: u32,
 }
 
-pub fn de_string_to_bytes<'de, D>(de: D) -> Result<Vec<u8>, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let s: &str = Deserialize::deserialize(de)?;
-    Ok(s.as_bytes().to_vec())
+/// Parse a decimal string such as `"5.2193847362"` into a `Price` with `decimals` decimals.
+///   Extra decimals are truncated.
+pub fn parse_price(bytes: &[u8], decimals: u32) -> Option<Price> {
+    if bytes.is_empty() {
+        return None;
+    }
+
+    let mut integer: Price = 0;
+    let mut fraction: Price = 0;
+    let mut fraction_digits = 0;
+    let mut seen_dot = false;
+
+    for &b in bytes {
+        match b {
+            b'.' if !seen_dot => seen_dot = true,
+            b'0'..=b'9' if !seen_dot => {
+                integer = integer.checked_mul(10)?.checked_add((b - b'0') as Price)?;
+            }
+            b'0'..=b'9' => {
+                if fraction_digits < decimals {
+                    fraction = fraction * 10 + (b - b'0') as Price;
+                    fraction_digits += 1;
+                }
+            }
+            _ => return None,
+        }
+    }
+
+    let fraction = fraction.checked_mul(10u64.checked_pow(decimals - fraction_digits)?)?;
+    integer
+        .checked_mul(10u64.checked_pow(decimals)?)?
+        .checked_add(fraction)
 }
 
-impl fmt::Debug for GithubInfo {
-    // `fmt` converts the vector of bytes inside the struct back to string for
-    //   more friendly display.
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{{ login: {}, blog: {}, public_repos: {} }}",
-            str::from_utf8(&self.login).map_err(|_| fmt::Error)?,
-            str::from_utf8(&self.blog).map_err(|_| fmt::Error)?,
-            &self.public_repos
-        )
+/// Follow the dot separated `path` into the JSON document `json` and parse the value found
+///   there, either a string or a number, into a `Price` with `decimals` decimals.
+pub fn extract_price(json: &[u8], path: &[u8], decimals: u32) -> Option<Price> {
+    let root: serde_json::Value = serde_json::from_slice(json).ok()?;
+    let mut value = &root;
+    if !path.is_empty() {
+        for key in path.split(|b| *b == b'.') {
+            let key = str::from_utf8(key).ok()?;
+            value = match value {
+                serde_json::Value::Array(items) => items.get(key.parse::<usize>().ok()?)?,
+                _ => value.get(key)?,
+            };
+        }
+    }
+
+    match value {
+        serde_json::Value::String(s) => parse_price(s.as_bytes(), decimals),
+        // Floats such as `5.21` print as decimals, negative or exponent notation is rejected.
+        serde_json::Value::Number(n) => parse_price(n.to_string().as_bytes(), decimals),
+        _ => None,
     }
 }
 
-impl fmt::Debug for DotPriceInfo {
-    // `fmt` converts the vector of bytes inside the struct back to string for
-    //   more friendly display.
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{{ price_usd: {}}}",
-            str::from_utf8(&self.price_usd).map_err(|_| fmt::Error)?,
-        )
+/// Median of the given prices. The prices are sorted in place.
+pub fn median(prices: &mut [Price]) -> Option<Price> {
+    if prices.is_empty() {
+        return None;
+    }
+    prices.sort_unstable();
+    let mid = prices.len() / 2;
+    if prices.len() % 2 == 0 {
+        // average without overflowing
+        let (a, b) = (prices[mid - 1], prices[mid]);
+        Some(a / 2 + b / 2 + (a % 2 + b % 2) / 2)
+    } else {
+        Some(prices[mid])
     }
 }
 
@@ -156,13 +271,53 @@ pub trait Trait: system::Trait + CreateSignedTransaction<Call<Self>> {
     type Call: From<Call<Self>>;
     /// The overarching event type.
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    /// Number of oracle submissions needed before a round is finalized.
+    type Quorum: Get<u32>;
+    /// Maximum distance from the round median before a submission is rejected as an outlier.
+    type MaxDeviation: Get<Permill>;
+    /// Blocks a round stays open after its first submission. Unless every oracle holding a
+    ///   session key has submitted, the round is only finalized once this window is over.
+    type SubmissionWindow: Get<Self::BlockNumber>;
+    /// Only these keys can send signed payloads.
+    type SessionAuthorities: SessionAuthorities<Self::Public>;
+    /// Origin allowed to manage feeds and oracles.
+    type ManageOrigin: EnsureOrigin<Self::Origin>;
 }
 
 decl_storage! {
     trait Store for Module<T: Trait> as Example {
         /// A vector of recently submitted numbers. Bounded by NUM_VEC_LEN
         Numbers get(fn numbers): VecDeque<u32>;
-        DotPrices get(fn dot_prices): Vec<DotPriceInfo>;
+        /// Recently finalized prices per asset symbol. Bounded by NUM_VEC_LEN
+        Prices get(fn prices): map hasher(blake2_128_concat) Symbol => VecDeque<Price>;
+
+        /// Price observations per asset symbol, oldest first. Bounded by MAX_OBSERVATIONS
+        Observations get(fn observations):
+            map hasher(blake2_128_concat) Symbol => VecDeque<Observation<T::BlockNumber>>;
+
+        /// Price feeds fetched by the offchain worker, managed by `ManageOrigin`.
+        Feeds get(fn feeds): map hasher(blake2_128_concat) Symbol => Option<FeedConfig<T::BlockNumber>>;
+
+        /// Accounts allowed to submit prices, managed by `ManageOrigin`.
+        Oracles get(fn oracles) config(): Vec<T::AccountId>;
+        /// The round currently collecting submissions, per asset symbol.
+        CurrentRound get(fn current_round): map hasher(blake2_128_concat) Symbol => RoundIndex;
+        /// Prices submitted to the current round so far, per asset symbol.
+        RoundSubmissions get(fn round_submissions):
+            map hasher(blake2_128_concat) Symbol => Vec<(T::AccountId, Price)>;
+        /// Block of the first submission to the current round, per asset symbol.
+        RoundStart get(fn round_start): map hasher(blake2_128_concat) Symbol => Option<T::BlockNumber>;
+        /// Missed and rejected rounds per oracle.
+        Penalties get(fn penalties): map hasher(blake2_128_concat) T::AccountId => OraclePenalty;
+    }
+    add_extra_genesis {
+        /// Initial feeds as `(symbol, urls, json_path, decimals, period)`.
+        config(feeds): Vec<(Symbol, Vec<Vec<u8>>, Vec<u8>, u8, T::BlockNumber)>;
+        build(|config: &GenesisConfig<T>| {
+            for (symbol, urls, json_path, decimals, period) in config.feeds.iter().cloned() {
+                Feeds::<T>::insert(symbol, FeedConfig { urls, json_path, decimals, period });
+            }
+        });
     }
 }
 
@@ -174,32 +329,59 @@ decl_event!(
     {
         /// Event generated when a new number is accepted to contribute to the average.
         NewNumber(Option<AccountId>, u32),
-        NewDotPrice(DotPriceInfo),
+        /// An oracle submitted a price to a round. [oracle, symbol, round, price]
+        PriceSubmitted(AccountId, Symbol, RoundIndex, Price),
+        /// A round reached quorum and its median became the new price. [symbol, round, price]
+        NewPrice(Symbol, RoundIndex, Price),
+        /// A price feed was added or updated. [symbol]
+        FeedSet(Symbol),
+        /// A price feed was removed. [symbol]
+        FeedRemoved(Symbol),
+        /// An oracle was added to the oracle set. [oracle]
+        OracleAdded(AccountId),
+        /// An oracle was removed from the oracle set. [oracle]
+        OracleRemoved(AccountId),
     }
 );
 
 decl_error! {
     pub enum Error for Module<T: Trait> {
-        // Error returned when not sure which ocw function to executed
-        UnknownOffchainMux,
-
-        // Error returned when making signed transactions in off-chain worker
+        // Error returned when making transactions in off-chain worker
         NoLocalAcctForSigning,
-        OffchainSignedTxError,
 
-        // Error returned when making unsigned transactions in off-chain worker
-        OffchainUnsignedTxError,
-
-        // Error returned when making unsigned transactions with signed payloads in off-chain worker
-        OffchainUnsignedTxSignedPayloadError,
-
-        // Error returned when fetching github info
+        // Errors returned when fetching a price feed
         HttpFetchingError,
+        HttpTimeout,
+        HttpStatusError,
+        HttpResponseTooLarge,
+        HttpBackingOff,
+
+        // Error returned when the fetched price can not be turned into a `Price`
+        PriceParsingError,
+
+        /// The account is already an oracle.
+        AlreadyOracle,
+        /// The account is not an oracle.
+        NotOracle,
+        /// The submission is for a round other than the current one.
+        WrongRound,
+        /// The oracle already submitted a price to the current round.
+        AlreadySubmitted,
+        /// There is no feed for the given symbol.
+        UnknownFeed,
+        /// The feed has no URL, more than `MAX_FEED_DECIMALS` decimals or a zero period.
+        InvalidFeed,
     }
 }
 
 decl_module! {
     pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        type Error = Error<T>;
+
+        const Quorum: u32 = T::Quorum::get();
+        const MaxDeviation: Permill = T::MaxDeviation::get();
+        const SubmissionWindow: T::BlockNumber = T::SubmissionWindow::get();
+
         fn deposit_event() = default;
 
         #[weight = 10000]
@@ -222,16 +404,6 @@ decl_module! {
             Ok(())
         }
 
-        #[weight = 10000]
-        pub fn submit_dot_price_unsigned(origin, price: DotPriceInfo) -> DispatchResult {
-            let _ = ensure_none(origin)?;
-            debug::info!("submit_dot_price_unsigned: {}", price);
-            Self::append_or_replace_dot_price(number);
-
-            Self::deposit_event(RawEvent::NewDotPrice(None, price));
-            Ok(())
-        }
-
         #[weight = 10000]
         pub fn submit_number_unsigned_with_signed_payload(origin, payload: Payload<T::Public>,
             _signature: T::Signature) -> DispatchResult
@@ -247,9 +419,118 @@ decl_module! {
             Ok(())
         }
 
+        /// Submit an oracle's price for the current round of a feed. The round is finalized and
+        ///   its median appended to `Prices` once all eligible oracles have submitted, or once
+        ///   `T::SubmissionWindow` is over and at least `quorum()` oracles have submitted.
+        #[weight = 10000]
+        pub fn submit_price_unsigned_with_signed_payload(origin, payload: PricePayload<T::Public>,
+            _signature: T::Signature) -> DispatchResult
+        {
+            let _ = ensure_none(origin)?;
+            // The signature is verified in `validate_unsigned`.
+            let PricePayload { symbol, round, price, public } = payload;
+            let oracle = public.into_account();
+            debug::info!("submit_price_unsigned_with_signed_payload: ({:?}, {}, {}, {:?})",
+                symbol, round, price, oracle);
+
+            ensure!(Self::is_oracle(&oracle), Error::<T>::NotOracle);
+            ensure!(Feeds::<T>::contains_key(&symbol), Error::<T>::UnknownFeed);
+            ensure!(round == Self::current_round(&symbol), Error::<T>::WrongRound);
+            ensure!(!Self::has_submitted(&symbol, &oracle), Error::<T>::AlreadySubmitted);
+
+            if !RoundStart::<T>::contains_key(&symbol) {
+                RoundStart::<T>::insert(&symbol, <system::Module<T>>::block_number());
+            }
+            RoundSubmissions::<T>::append(&symbol, (oracle.clone(), price));
+            Self::deposit_event(RawEvent::PriceSubmitted(oracle, symbol.clone(), round, price));
+
+            let submitted = RoundSubmissions::<T>::decode_len(&symbol).unwrap_or(0) as u32;
+            // Nobody else can submit, so there is no need to wait for the window to close.
+            let everyone_submitted = submitted >= Self::eligible_oracles().max(1);
+            if everyone_submitted || Self::round_can_close(&symbol, submitted) {
+                Self::finalize_round(&symbol);
+            }
+            Ok(())
+        }
+
+        /// Add a price feed or replace the configuration of an existing one.
+        #[weight = 10000]
+        pub fn set_feed(origin, symbol: Symbol, feed: FeedConfig<T::BlockNumber>) -> DispatchResult {
+            T::ManageOrigin::ensure_origin(origin)?;
+            ensure!(
+                !feed.urls.is_empty() && feed.decimals <= MAX_FEED_DECIMALS && !feed.period.is_zero(),
+                Error::<T>::InvalidFeed
+            );
+            Feeds::<T>::insert(&symbol, feed);
+
+            Self::deposit_event(RawEvent::FeedSet(symbol));
+            Ok(())
+        }
+
+        /// Remove a price feed together with its pending round. Past prices are kept.
+        #[weight = 10000]
+        pub fn remove_feed(origin, symbol: Symbol) -> DispatchResult {
+            T::ManageOrigin::ensure_origin(origin)?;
+            ensure!(Feeds::<T>::contains_key(&symbol), Error::<T>::UnknownFeed);
+            Feeds::<T>::remove(&symbol);
+            RoundSubmissions::<T>::remove(&symbol);
+            RoundStart::<T>::remove(&symbol);
+
+            Self::deposit_event(RawEvent::FeedRemoved(symbol));
+            Ok(())
+        }
+
+        #[weight = 10000]
+        pub fn add_oracle(origin, oracle: T::AccountId) -> DispatchResult {
+            T::ManageOrigin::ensure_origin(origin)?;
+            Oracles::<T>::try_mutate(|oracles| -> DispatchResult {
+                ensure!(!oracles.contains(&oracle), Error::<T>::AlreadyOracle);
+                oracles.push(oracle.clone());
+                Ok(())
+            })?;
+
+            Self::deposit_event(RawEvent::OracleAdded(oracle));
+            Ok(())
+        }
+
+        #[weight = 10000]
+        pub fn remove_oracle(origin, oracle: T::AccountId) -> DispatchResult {
+            T::ManageOrigin::ensure_origin(origin)?;
+            Oracles::<T>::try_mutate(|oracles| -> DispatchResult {
+                let pos = oracles.iter().position(|o| o == &oracle).ok_or(Error::<T>::NotOracle)?;
+                oracles.swap_remove(pos);
+                Ok(())
+            })?;
+            // A removed oracle's pending submissions no longer count towards the quorum.
+            for (symbol, _) in Feeds::<T>::iter() {
+                RoundSubmissions::<T>::mutate(&symbol, |subs| subs.retain(|(who, _)| who != &oracle));
+                if Self::round_submissions(&symbol).is_empty() {
+                    RoundStart::<T>::remove(&symbol);
+                }
+            }
+
+            Self::deposit_event(RawEvent::OracleRemoved(oracle));
+            Ok(())
+        }
+
+        /// Finalize the rounds whose submission window is over and that reached quorum.
+        fn on_initialize(_now: T::BlockNumber) -> Weight {
+            let open: Vec<Symbol> = RoundStart::<T>::iter().map(|(symbol, _)| symbol).collect();
+            let mut finalized = 0u64;
+            for symbol in open.iter() {
+                let submitted = RoundSubmissions::<T>::decode_len(symbol).unwrap_or(0) as u32;
+                if Self::round_can_close(symbol, submitted) {
+                    Self::finalize_round(symbol);
+                    finalized += 1;
+                }
+            }
+            T::DbWeight::get().reads_writes(2 * open.len() as u64 + 1, 5 * finalized)
+        }
+
         fn offchain_worker(block_number: T::BlockNumber) {
             debug::info!("Entering off-chain worker");
-            Self::offchain_unsigned_tx(block_number);
+
+            let result = Self::offchain_price_round(block_number);
             if let Err(e) = result {
                 debug::error!("offchain_worker error: {:?}", e);
             }
@@ -270,307 +551,264 @@ impl<T: Trait> Module<T> {
         });
     }
 
-    fn append_or_replace_dot_price(price: DotPriceInfo) {
-        DotPrices::mutate(|prices| {
+    fn append_or_replace_price(symbol: &[u8], price: Price) {
+        Prices::mutate(symbol, |prices| {
             if prices.len() == NUM_VEC_LEN {
                 let _ = prices.pop_front();
             }
             prices.push_back(price);
-            debug::info!("DotPrices vector: {:?}", prices);
+            debug::info!("Prices vector: {:?}", prices);
         });
     }
 
-    /// Check if we have fetched github info before. If yes, we can use the cached version
-    ///   stored in off-chain worker storage `storage`. If not, we fetch the remote info and
-    ///   write the info into the storage for future retrieval.
-    fn fetch_github_info() -> Result<(), Error<T>> {
-        // Create a reference to Local Storage value.
-        // Since the local storage is common for all offchain workers, it's a good practice
-        // to prepend our entry with the pallet name.
-        let s_info = StorageValueRef::persistent(b"offchain-demo::gh-info");
-
-        // Local storage is persisted and shared between runs of the offchain workers,
-        // offchain workers may run concurrently. We can use the `mutate` function to
-        // write a storage entry in an atomic fashion.
-        //
-        // With a similar API as `StorageValue` with the variables `get`, `set`, `mutate`.
-        // We will likely want to use `mutate` to access
-        // the storage comprehensively.
-        //
-        // Ref: https://substrate.dev/rustdocs/v2.0.0/sp_runtime/offchain/storage/struct.StorageValueRef.html
-        if let Some(Some(gh_info)) = s_info.get::<GithubInfo>() {
-            // gh-info has already been fetched. Return early.
-            debug::info!("cached gh-info: {:?}", gh_info);
-            return Ok(());
-        }
-
-        // Since off-chain storage can be accessed by off-chain workers from multiple runs, it is important to lock
-        //   it before doing heavy computations or write operations.
-        // ref: https://substrate.dev/rustdocs/v2.0.0-rc3/sp_runtime/offchain/storage_lock/index.html
-        //
-        // There are four ways of defining a lock:
-        //   1) `new` - lock with default time and block exipration
-        //   2) `with_deadline` - lock with default block but custom time expiration
-        //   3) `with_block_deadline` - lock with default time but custom block expiration
-        //   4) `with_block_and_time_deadline` - lock with custom time and block expiration
-        // Here we choose the most custom one for demonstration purpose.
-        let mut lock = StorageLock::<BlockAndTime<Self>>::with_block_and_time_deadline(
-            b"offchain-demo::lock",
-            LOCK_BLOCK_EXPIRATION,
-            rt_offchain::Duration::from_millis(LOCK_TIMEOUT_EXPIRATION),
-        );
-
-        // We try to acquire the lock here. If failed, we know the `fetch_n_parse` part inside is being
-        //   executed by previous run of ocw, so the function just returns.
-        // ref: https://substrate.dev/rustdocs/v2.0.0/sp_runtime/offchain/storage_lock/struct.StorageLock.html#method.try_lock
-        if let Ok(_guard) = lock.try_lock() {
-            match Self::fetch_n_parse() {
-                Ok(gh_info) => {
-                    s_info.set(&gh_info);
-                }
-                Err(err) => {
-                    return Err(err);
-                }
+    /// Accumulate the previous price up to the current block and start a new observation.
+    fn record_observation(symbol: &[u8], price: Price) {
+        let now = <frame_system::Module<T>>::block_number();
+        Observations::<T>::mutate(symbol, |observations| {
+            let cumulative = observations
+                .back()
+                .map(|last| Self::cumulative_at(last, now))
+                .unwrap_or(0);
+            // Several rounds finalized in one block only keep the last price.
+            if observations.back().map_or(false, |last| last.block == now) {
+                let _ = observations.pop_back();
             }
-        }
-        Ok(())
-    }
-
-    fn fetch_dot_price() -> Result<(), Error<T>> {
-        // Create a reference to Local Storage value.
-        // Since the local storage is common for all offchain workers, it's a good practice
-        // to prepend our entry with the pallet name.
-        let s_info = StorageValueRef::persistent(b"offchain-demo::dot-price");
-
-        // Local storage is persisted and shared between runs of the offchain workers,
-        // offchain workers may run concurrently. We can use the `mutate` function to
-        // write a storage entry in an atomic fashion.
-        //
-        // With a similar API as `StorageValue` with the variables `get`, `set`, `mutate`.
-        // We will likely want to use `mutate` to access
-        // the storage comprehensively.
-        //
-        // Ref: https://substrate.dev/rustdocs/v2.0.0/sp_runtime/offchain/storage/struct.StorageValueRef.html
-        if let Some(Some(gh_info)) = s_info.get::<GithubInfo>() {
-            // gh-info has already been fetched. Return early.
-            debug::info!("cached dot-price: {:?}", gh_info);
-            return Ok(());
-        }
-
-        // Since off-chain storage can be accessed by off-chain workers from multiple runs, it is important to lock
-        //   it before doing heavy computations or write operations.
-        // ref: https://substrate.dev/rustdocs/v2.0.0-rc3/sp_runtime/offchain/storage_lock/index.html
-        //
-        // There are four ways of defining a lock:
-        //   1) `new` - lock with default time and block exipration
-        //   2) `with_deadline` - lock with default block but custom time expiration
-        //   3) `with_block_deadline` - lock with default time but custom block expiration
-        //   4) `with_block_and_time_deadline` - lock with custom time and block expiration
-        // Here we choose the most custom one for demonstration purpose.
-        let mut lock = StorageLock::<BlockAndTime<Self>>::with_block_and_time_deadline(
-            b"offchain-demo::lock",
-            LOCK_BLOCK_EXPIRATION,
-            rt_offchain::Duration::from_millis(LOCK_TIMEOUT_EXPIRATION),
-        );
-
-        // We try to acquire the lock here. If failed, we know the `fetch_n_parse` part inside is being
-        //   executed by previous run of ocw, so the function just returns.
-        // ref: https://substrate.dev/rustdocs/v2.0.0/sp_runtime/offchain/storage_lock/struct.StorageLock.html#method.try_lock
-        if let Ok(_guard) = lock.try_lock() {
-            match Self::fetch_n_parse() {
-                Ok(gh_info) => {
-                    s_info.set(&gh_info);
-                }
-                Err(err) => {
-                    return Err(err);
-                }
+            if observations.len() == MAX_OBSERVATIONS {
+                let _ = observations.pop_front();
             }
-        }
-        Ok(())
+            observations.push_back(Observation { block: now, price, cumulative });
+        });
     }
 
-    /// Fetch from remote and deserialize the JSON to a struct
-    fn fetch_n_parse() -> Result<GithubInfo, Error<T>> {
-        let resp_bytes = Self::fetch_from_remote().map_err(|e| {
-            debug::error!("fetch_from_remote error: {:?}", e);
-            <Error<T>>::HttpFetchingError
-        })?;
-
-        let resp_str = str::from_utf8(&resp_bytes).map_err(|_| <Error<T>>::HttpFetchingError)?;
-        // Print out our fetched JSON string
-        debug::info!("{}", resp_str);
-
-        // Deserializing JSON to struct, thanks to `serde` and `serde_derive`
-        let gh_info: GithubInfo =
-            serde_json::from_str(&resp_str).map_err(|_| <Error<T>>::HttpFetchingError)?;
-        Ok(gh_info)
+    /// The cumulative price at block `at`, assuming the price of `observation` held since.
+    fn cumulative_at(observation: &Observation<T::BlockNumber>, at: T::BlockNumber) -> u128 {
+        let blocks = at.saturating_sub(observation.block).saturated_into::<u128>();
+        observation
+            .cumulative
+            .saturating_add((observation.price as u128).saturating_mul(blocks))
     }
 
-    fn fetch_dot_price_parse() -> Result<DotPriceInfo, Error<T>> {
-        let resp_bytes = Self::fetch_dot_price_from_remote().map_err(|e| {
-            debug::error!("fetch_from_remote error: {:?}", e);
-            <Error<T>>::HttpFetchingError
-        })?;
+    /// The last finalized price of `symbol`, `None` before its first round is finalized.
+    pub fn latest_price(symbol: &[u8]) -> Option<FinalizedPrice> {
+        let price = *Self::prices(symbol).back()?;
+        Some(FinalizedPrice {
+            // Finalizing a round always moves to the next one.
+            round: Self::current_round(symbol).wrapping_sub(1),
+            price,
+            decimals: Self::feeds(symbol).map_or(0, |feed| feed.decimals),
+        })
+    }
 
-        let resp_str = str::from_utf8(&resp_bytes).map_err(|_| <Error<T>>::HttpFetchingError)?;
-        // Print out our fetched JSON string
-        debug::info!("{}", resp_str);
+    /// Up to the `n` most recent finalized prices of `symbol`, oldest first.
+    pub fn price_history(symbol: &[u8], n: u32) -> Vec<Price> {
+        let prices = Self::prices(symbol);
+        let skip = prices.len().saturating_sub(n as usize);
+        prices.into_iter().skip(skip).collect()
+    }
 
-        // Deserializing JSON to struct, thanks to `serde` and `serde_derive`
-        let gh_info: DotPriceInfo =
-            serde_json::from_str(&resp_str).map_err(|_| <Error<T>>::HttpFetchingError)?;
-        Ok(gh_info)
+    pub fn is_oracle(who: &T::AccountId) -> bool {
+        Self::oracles().contains(who)
     }
 
-    /// This function uses the `offchain::http` API to query the remote github information,
-    ///   and returns the JSON response as vector of bytes.
-    fn fetch_from_remote() -> Result<Vec<u8>, Error<T>> {
-        debug::info!("sending request to: {}", HTTP_REMOTE_REQUEST);
+    fn has_submitted(symbol: &[u8], who: &T::AccountId) -> bool {
+        Self::round_submissions(symbol).iter().any(|(o, _)| o == who)
+    }
 
-        // Initiate an external HTTP GET request. This is using high-level wrappers from `sp_runtime`.
-        let request = rt_offchain::http::Request::get(HTTP_REMOTE_REQUEST);
+    /// Whether the submission window of the current round of `symbol` is over and
+    ///   `submitted` prices are enough to finalize it.
+    fn round_can_close(symbol: &[u8], submitted: u32) -> bool {
+        let window_over = Self::round_start(symbol).map_or(false, |start| {
+            <system::Module<T>>::block_number() >= start.saturating_add(T::SubmissionWindow::get())
+        });
+        window_over && submitted >= Self::quorum()
+    }
 
-        // Keeping the offchain worker execution time reasonable, so limiting the call to be within 3s.
-        let timeout = sp_io::offchain::timestamp()
-            .add(rt_offchain::Duration::from_millis(FETCH_TIMEOUT_PERIOD));
+    /// Close the current round of a feed: take the median of all submissions, drop the ones
+    ///   further than `T::MaxDeviation` from it, and record the median of the remaining prices.
+    ///   Eligible oracles that did not submit, or whose price was dropped, get their penalty
+    ///   counters bumped.
+    fn finalize_round(symbol: &[u8]) {
+        let round = Self::current_round(symbol);
+        let submissions = RoundSubmissions::<T>::take(symbol);
+        RoundStart::<T>::remove(symbol);
+
+        let mut prices: Vec<Price> = submissions.iter().map(|(_, p)| *p).collect();
+        let first_median = match median(&mut prices) {
+            Some(m) => m,
+            None => return,
+        };
+        let max_deviation = T::MaxDeviation::get() * first_median;
+
+        let mut accepted = Vec::with_capacity(submissions.len());
+        for (oracle, price) in submissions.iter() {
+            let deviation = if *price > first_median {
+                price - first_median
+            } else {
+                first_median - price
+            };
+            if deviation > max_deviation {
+                debug::warn!("{:?} round {}: rejecting outlier {} from {:?}", symbol, round, price, oracle);
+                Penalties::<T>::mutate(oracle, |p| p.deviated = p.deviated.saturating_add(1));
+            } else {
+                accepted.push(*price);
+            }
+        }
 
-        // For github API request, we also need to specify `user-agent` in http request header.
-        //   See: https://developer.github.com/v3/#user-agent-required
-        let pending = request
-            .add_header("User-Agent", HTTP_HEADER_USER_AGENT)
-            .deadline(timeout) // Setting the timeout time
-            .send() // Sending the request out by the host
-            .map_err(|_| <Error<T>>::HttpFetchingError)?;
-
-        // By default, the http request is async from the runtime perspective. So we are asking the
-        //   runtime to wait here.
-        // The returning value here is a `Result` of `Result`, so we are unwrapping it twice by two `?`
-        //   ref: https://substrate.dev/rustdocs/v2.0.0/sp_runtime/offchain/http/struct.PendingRequest.html#method.try_wait
-        let response = pending
-            .try_wait(timeout)
-            .map_err(|_| <Error<T>>::HttpFetchingError)?
-            .map_err(|_| <Error<T>>::HttpFetchingError)?;
-
-        if response.code != 200 {
-            debug::error!("Unexpected http request status code: {}", response.code);
-            return Err(<Error<T>>::HttpFetchingError);
+        // Oracles without a session key cannot submit, so only the eligible ones miss a round.
+        for public in T::SessionAuthorities::session_authorities() {
+            let oracle = public.into_account();
+            if Self::is_oracle(&oracle) && !submissions.iter().any(|(o, _)| o == &oracle) {
+                Penalties::<T>::mutate(&oracle, |p| p.missed = p.missed.saturating_add(1));
+            }
         }
 
-        // Next we fully read the response body and collect it to a vector of bytes.
-        Ok(response.body().collect::<Vec<u8>>())
-    }
+        // `accepted` always contains at least the submission(s) sitting on the median.
+        let price = median(&mut accepted).unwrap_or(first_median);
+        Self::append_or_replace_price(symbol, price);
+        Self::record_observation(symbol, price);
+        CurrentRound::insert(symbol, round.wrapping_add(1));
 
-    fn fetch_dot_price_from_remote() -> Result<Vec<u8>, Error<T>> {
-        debug::info!("sending request to: {}", DOT_PRICE_HTTP_REMOTE_REQUEST);
+        Self::deposit_event(RawEvent::NewPrice(symbol.to_vec(), round, price));
+    }
 
-        // Initiate an external HTTP GET request. This is using high-level wrappers from `sp_runtime`.
-        let request = rt_offchain::http::Request::get(DOT_PRICE_HTTP_REMOTE_REQUEST);
+    /// Fetch the JSON document of a feed and extract its price.
+    fn fetch_feed_price(
+        symbol: &[u8],
+        feed: &FeedConfig<T::BlockNumber>,
+        block_number: T::BlockNumber,
+    ) -> Result<Price, Error<T>> {
+        let resp_bytes = Self::fetch_feed_from_remote(symbol, &feed.urls, block_number)?;
 
-        // Keeping the offchain worker execution time reasonable, so limiting the call to be within 3s.
-        let timeout = sp_io::offchain::timestamp()
-            .add(rt_offchain::Duration::from_millis(FETCH_TIMEOUT_PERIOD));
+        extract_price(&resp_bytes, &feed.json_path, feed.decimals as u32)
+            .ok_or(<Error<T>>::PriceParsingError)
+    }
 
-        // For github API request, we also need to specify `user-agent` in http request header.
-        //   See: https://developer.github.com/v3/#user-agent-required
-        let pending = request
+    /// Fetch a feed from the first of its `urls` that answers. Each symbol backs off on its own,
+    ///   so one failing feed does not hold back the others.
+    fn fetch_feed_from_remote(
+        symbol: &[u8],
+        urls: &[Vec<u8>],
+        block_number: T::BlockNumber,
+    ) -> Result<Vec<u8>, Error<T>> {
+        let id = [&b"dot-price::feed::"[..], symbol].concat();
+        let client = urls
+            .iter()
+            .filter_map(|url| str::from_utf8(url).ok())
+            .fold(HttpClient::new(&id), |client, url| client.add_url(url));
+
+        client
             .add_header("User-Agent", HTTP_HEADER_USER_AGENT)
-            .deadline(timeout) // Setting the timeout time
-            .send() // Sending the request out by the host
-            .map_err(|_| <Error<T>>::HttpFetchingError)?;
-
-        // By default, the http request is async from the runtime perspective. So we are asking the
-        //   runtime to wait here.
-        // The returning value here is a `Result` of `Result`, so we are unwrapping it twice by two `?`
-        //   ref: https://substrate.dev/rustdocs/v2.0.0/sp_runtime/offchain/http/struct.PendingRequest.html#method.try_wait
-        let response = pending
-            .try_wait(timeout)
-            .map_err(|_| <Error<T>>::HttpFetchingError)?
-            .map_err(|_| <Error<T>>::HttpFetchingError)?;
-
-        if response.code != 200 {
-            debug::error!("Unexpected http request status code: {}", response.code);
-            return Err(<Error<T>>::HttpFetchingError);
+            .timeout(FETCH_TIMEOUT_PERIOD)
+            .fetch(block_number)
+            .map_err(Self::http_error)
+    }
+
+    /// Map a failed fetch to the error of this pallet.
+    fn http_error(e: HttpError) -> Error<T> {
+        debug::error!("http fetching error: {:?}", e);
+        match e {
+            HttpError::Unreachable => <Error<T>>::HttpFetchingError,
+            HttpError::Timeout => <Error<T>>::HttpTimeout,
+            HttpError::Status(_) => <Error<T>>::HttpStatusError,
+            HttpError::TooLarge => <Error<T>>::HttpResponseTooLarge,
+            HttpError::BackingOff => <Error<T>>::HttpBackingOff,
+        }
+    }
+
+    /// Fetch the price of every feed that is due at `block_number` and submit it to the feed's
+    ///   current round, signed by every local oracle key. Keys that are not in `Oracles` are
+    ///   rejected by `validate_unsigned`.
+    fn offchain_price_round(block_number: T::BlockNumber) -> Result<(), Error<T>> {
+        let signer = Signer::<T, T::AuthorityId>::all_accounts();
+        if !signer.can_sign() {
+            debug::error!("No local account available");
+            return Err(<Error<T>>::NoLocalAcctForSigning);
         }
 
-        // Next we fully read the response body and collect it to a vector of bytes.
-        Ok(response.body().collect::<Vec<u8>>())
-    }
-
-    fn offchain_signed_tx(block_number: T::BlockNumber) -> Result<(), Error<T>> {
-        // We retrieve a signer and check if it is valid.
-        //   Since this pallet only has one key in the keystore. We use `any_account()1 to
-        //   retrieve it. If there are multiple keys and we want to pinpoint it, `with_filter()` can be chained,
-        //   ref: https://substrate.dev/rustdocs/v2.0.0/frame_system/offchain/struct.Signer.html
-        let signer = Signer::<T, T::AuthorityId>::any_account();
-
-        // Translating the current block number to number and submit it on-chain
-        let number: u32 = block_number.try_into().unwrap_or(0) as u32;
-
-        // `result` is in the type of `Option<(Account<T>, Result<(), ()>)>`. It is:
-        //   - `None`: no account is available for sending transaction
-        //   - `Some((account, Ok(())))`: transaction is successfully sent
-        //   - `Some((account, Err(())))`: error occured when sending the transaction
-        let result = signer.send_signed_transaction(|_acct|
-			// This is the on-chain function
-			Call::submit_number_signed(number));
-
-        // Display error if the signed tx fails.
-        if let Some((acc, res)) = result {
-            if res.is_err() {
-                debug::error!("failure: offchain_signed_tx: tx sent: {:?}", acc.id);
-                return Err(<Error<T>>::OffchainSignedTxError);
+        for (symbol, feed) in Feeds::<T>::iter() {
+            if !Self::feed_is_due(&symbol, &feed, block_number) {
+                continue;
+            }
+
+            let round = Self::current_round(&symbol);
+            // A failed fetch is retried on the next block, the http client backs off on its own.
+            let price = match Self::fetch_feed_price(&symbol, &feed, block_number) {
+                Ok(price) => {
+                    Self::record_fetch(&symbol, block_number);
+                    price
+                }
+                Err(e) => {
+                    debug::error!("failed to fetch {:?}: {:?}", symbol, e);
+                    continue;
+                }
+            };
+
+            // `send_unsigned_transaction` on `all_accounts` returns one result per local key.
+            let results = signer.send_unsigned_transaction(
+                |acct| PricePayload {
+                    symbol: symbol.clone(),
+                    round,
+                    price,
+                    public: acct.public.clone(),
+                },
+                Call::submit_price_unsigned_with_signed_payload,
+            );
+            for (acc, res) in &results {
+                if res.is_err() {
+                    debug::warn!("{:?} price for round {} not accepted from {:?}", symbol, round, acc.id);
+                }
             }
-            // Transaction is sent successfully
-            return Ok(());
         }
+        Ok(())
+    }
 
-        // The case of `None`: no account is available for sending
-        debug::error!("No local account available");
-        Err(<Error<T>>::NoLocalAcctForSigning)
+    /// Whether `feed` has not been fetched successfully by this node within its period. The
+    ///   block of the last successful fetch is kept per symbol in offchain local storage.
+    fn feed_is_due(symbol: &[u8], feed: &FeedConfig<T::BlockNumber>, block_number: T::BlockNumber) -> bool {
+        let last_fetch = StorageValueRef::persistent(&Self::last_fetch_key(symbol));
+        match last_fetch.get::<T::BlockNumber>() {
+            Some(Some(last)) => block_number >= last.saturating_add(feed.period),
+            _ => true,
+        }
     }
 
-    fn offchain_unsigned_tx(block_number: T::BlockNumber) -> Result<(), Error<T>> {
-        //TODO:获取price价格
-        let dot_price = fetch_dot_price_parse()?;
+    /// Remember that the feed of `symbol` was fetched at `block_number`.
+    fn record_fetch(symbol: &[u8], block_number: T::BlockNumber) {
+        StorageValueRef::persistent(&Self::last_fetch_key(symbol)).set(&block_number);
+    }
 
-        let number: u32 = block_number.try_into().unwrap_or(0) as u32;
-        let call = Call::submit_dot_price_unsigned(dot_price);
+    fn last_fetch_key(symbol: &[u8]) -> Vec<u8> {
+        [&b"dot-price::last-fetch::"[..], symbol].concat()
+    }
+}
 
-        // `submit_unsigned_transaction` returns a type of `Result<(), ()>`
-        //   ref: https://substrate.dev/rustdocs/v2.0.0/frame_system/offchain/struct.SubmitTransaction.html#method.submit_unsigned_transaction
-        SubmitTransaction::<T, Call<T>>::submit_unsigned_transaction(call.into()).map_err(|_| {
-            debug::error!("Failed in offchain_unsigned_tx");
-            <Error<T>>::OffchainUnsignedTxError
-        })
+impl<T: Trait> PriceProvider<T::BlockNumber> for Module<T> {
+    fn latest(symbol: &[u8]) -> Option<Price> {
+        Self::prices(symbol).back().copied()
     }
 
-    fn offchain_unsigned_tx_signed_payload(block_number: T::BlockNumber) -> Result<(), Error<T>> {
-        // Retrieve the signer to sign the payload
-        let signer = Signer::<T, T::AuthorityId>::any_account();
-
-        let number: u32 = block_number.try_into().unwrap_or(0) as u32;
-
-        // `send_unsigned_transaction` is returning a type of `Option<(Account<T>, Result<(), ()>)>`.
-        //   Similar to `send_signed_transaction`, they account for:
-        //   - `None`: no account is available for sending transaction
-        //   - `Some((account, Ok(())))`: transaction is successfully sent
-        //   - `Some((account, Err(())))`: error occured when sending the transaction
-        if let Some((_, res)) = signer.send_unsigned_transaction(
-            |acct| Payload {
-                number,
-                public: acct.public.clone(),
-            },
-            Call::submit_number_unsigned_with_signed_payload,
-        ) {
-            return res.map_err(|_| {
-                debug::error!("Failed in offchain_unsigned_tx_signed_payload");
-                <Error<T>>::OffchainUnsignedTxSignedPayloadError
-            });
+    fn twap(symbol: &[u8], window: T::BlockNumber) -> Option<Price> {
+        let observations = Self::observations(symbol);
+        let now = <frame_system::Module<T>>::block_number();
+        if window.is_zero() {
+            return observations.back().map(|o| o.price);
         }
+        let start = now.checked_sub(&window)?;
+
+        // The observation in effect at a block is the last one recorded at or before it.
+        let in_effect = |at: T::BlockNumber| observations.iter().rev().find(|o| o.block <= at);
+        let cumulative_start = Self::cumulative_at(in_effect(start)?, start);
+        let cumulative_now = Self::cumulative_at(observations.back()?, now);
 
-        // The case of `None`: no account is available for sending
-        debug::error!("No local account available");
-        Err(<Error<T>>::NoLocalAcctForSigning)
+        let average = (cumulative_now - cumulative_start) / window.saturated_into::<u128>();
+        Some(average.saturated_into())
+    }
+
+    fn is_stale(symbol: &[u8], max_age: T::BlockNumber) -> bool {
+        let now = <frame_system::Module<T>>::block_number();
+        Self::observations(symbol)
+            .back()
+            .map_or(true, |last| now.saturating_sub(last.block) > max_age)
     }
 }
 
@@ -593,16 +831,55 @@ impl<T: Trait> frame_support::unsigned::ValidateUnsigned for Module<T> {
                 if !SignedPayload::<T>::verify::<T::AuthorityId>(payload, signature.clone()) {
                     return InvalidTransaction::BadProof.into();
                 }
+                if !Self::is_session_authority(&payload.public) {
+                    return InvalidTransaction::BadSigner.into();
+                }
                 valid_tx(b"submit_number_unsigned_with_signed_payload".to_vec())
             }
+            Call::submit_price_unsigned_with_signed_payload(ref payload, ref signature) => {
+                if !SignedPayload::<T>::verify::<T::AuthorityId>(payload, signature.clone()) {
+                    return InvalidTransaction::BadProof.into();
+                }
+                if !Self::is_session_authority(&payload.public) {
+                    return InvalidTransaction::BadSigner.into();
+                }
+                let oracle = payload.public.clone().into_account();
+                if !Self::is_oracle(&oracle) {
+                    return InvalidTransaction::BadProof.into();
+                }
+                if !Feeds::<T>::contains_key(&payload.symbol) {
+                    return InvalidTransaction::Call.into();
+                }
+                if payload.round != Self::current_round(&payload.symbol)
+                    || Self::has_submitted(&payload.symbol, &oracle)
+                {
+                    return InvalidTransaction::Stale.into();
+                }
+                // One submission per oracle per feed and round.
+                valid_tx((&payload.symbol, payload.round, oracle).encode())
+            }
             _ => InvalidTransaction::Call.into(),
         }
     }
 }
 
-impl<T: Trait> rt_offchain::storage_lock::BlockNumberProvider for Module<T> {
-    type BlockNumber = T::BlockNumber;
-    fn current_block_number() -> Self::BlockNumber {
-        <frame_system::Module<T>>::block_number()
+impl<T: Trait> Module<T> {
+    /// Whether `public` is the offchain worker key of a current session validator.
+    pub fn is_session_authority(public: &T::Public) -> bool {
+        T::SessionAuthorities::session_authorities().contains(public)
+    }
+
+    /// Number of oracles holding a session key, the only ones able to submit prices.
+    pub fn eligible_oracles() -> u32 {
+        T::SessionAuthorities::session_authorities()
+            .into_iter()
+            .filter(|public| Self::is_oracle(&public.clone().into_account()))
+            .count() as u32
+    }
+
+    /// Submissions needed to finalize a round once its window is over. This is `T::Quorum`
+    ///   capped by the number of eligible oracles, and at least one.
+    pub fn quorum() -> u32 {
+        T::Quorum::get().min(Self::eligible_oracles()).max(1)
     }
 }
@@ -1,5 +1,9 @@
 use crate::*;
-use frame_support::{assert_ok, impl_outer_event, impl_outer_origin, parameter_types};
+use frame_support::{
+	assert_noop, assert_ok, dispatch::DispatchResult, impl_outer_event, impl_outer_origin,
+	parameter_types, traits::OnInitialize,
+};
+use frame_system::EnsureRoot;
 use parity_scale_codec::{alloc::sync::Arc, Decode};
 use parking_lot::RwLock;
 use sp_core::{
@@ -13,10 +17,11 @@ use sp_core::{
 	H256,
 };
 use sp_io::TestExternalities;
+use std::cell::RefCell;
 use sp_runtime::{
 	testing::{Header, TestXt},
-	traits::{BlakeTwo256, IdentityLookup, Verify},
-	Perbill,
+	traits::{BadOrigin, BlakeTwo256, IdentityLookup, Verify},
+	Perbill, Permill,
 };
 
 use crate as dot_price;
@@ -77,12 +82,33 @@ type TestExtrinsic = TestXt<Call<TestRuntime>, ()>;
 
 parameter_types! {
 	pub const UnsignedPriority: u64 = 100;
+	pub const Quorum: u32 = 3;
+	pub const MaxDeviation: Permill = Permill::from_percent(10);
+	pub const SubmissionWindow: u64 = 5;
+}
+
+thread_local! {
+	static SESSION_AUTHORITIES: RefCell<Vec<sr25519::Public>> = RefCell::new(vec![]);
+}
+
+/// Stands in for the session keys kept by ocw-demo in the runtime.
+pub struct TestSessionAuthorities;
+
+impl SessionAuthorities<sr25519::Public> for TestSessionAuthorities {
+	fn session_authorities() -> Vec<sr25519::Public> {
+		SESSION_AUTHORITIES.with(|v| v.borrow().clone())
+	}
 }
 
 impl Trait for TestRuntime {
 	type AuthorityId = crypto::TestAuthId;
 	type Call = Call<TestRuntime>;
 	type Event = TestEvent;
+	type Quorum = Quorum;
+	type MaxDeviation = MaxDeviation;
+	type SubmissionWindow = SubmissionWindow;
+	type SessionAuthorities = TestSessionAuthorities;
+	type ManageOrigin = EnsureRoot<Self::AccountId>;
 }
 
 impl<LocalCall> system::offchain::CreateSignedTransaction<LocalCall> for TestRuntime
@@ -117,6 +143,46 @@ where
 
 pub type System = system::Module<TestRuntime>;
 pub type OcwDemo = Module<TestRuntime>;
+pub type DotPrice = Module<TestRuntime>;
+
+fn account(n: u8) -> sr25519::Public {
+	sr25519::Public::from_raw([n; 32])
+}
+
+const DOT: &[u8] = b"DOT";
+
+fn dot_feed() -> FeedConfig<u64> {
+	FeedConfig {
+		urls: vec![
+			b"https://api.coincap.io/v2/assets/polkadot".to_vec(),
+			b"https://coincap.mirror.example/v2/assets/polkadot".to_vec(),
+		],
+		json_path: b"data.priceUsd".to_vec(),
+		decimals: 6,
+		period: 5,
+	}
+}
+
+fn submit_price(oracle: sr25519::Public, round: RoundIndex, price: Price) -> DispatchResult {
+	DotPrice::submit_price_unsigned_with_signed_payload(
+		Origin::none(),
+		PricePayload { symbol: DOT.to_vec(), round, price, public: oracle },
+		Default::default(),
+	)
+}
+
+fn setup_oracles(n: u8) {
+	assert_ok!(DotPrice::set_feed(Origin::root(), DOT.to_vec(), dot_feed()));
+	for i in 1..=n {
+		assert_ok!(DotPrice::add_oracle(Origin::root(), account(i)));
+	}
+	set_session_authorities(1..=n);
+}
+
+fn set_session_authorities(accounts: impl Iterator<Item = u8>) {
+	let keys = accounts.map(account).collect::<Vec<_>>();
+	SESSION_AUTHORITIES.with(|v| *v.borrow_mut() = keys);
+}
 
 struct ExternalityBuilder;
 
@@ -146,6 +212,7 @@ impl ExternalityBuilder {
 		t.register_extension(TransactionPoolExt::new(pool));
 		t.register_extension(KeystoreExt(keystore));
 		t.execute_with(|| System::set_block_number(1));
+		set_session_authorities(None.into_iter());
 		(t, pool_state, offchain_state)
 	}
 }
@@ -180,36 +247,406 @@ fn submit_number_signed_works() {
 }
 
 #[test]
-fn test_offchain_signed_tx() {
-	let (mut t, pool_state, _offchain_state) = ExternalityBuilder::build();
+fn parse_price_works() {
+	assert_eq!(parse_price(b"5.2193847362", 6), Some(5_219_384));
+	assert_eq!(parse_price(b"5.2", 6), Some(5_200_000));
+	assert_eq!(parse_price(b"12", 6), Some(12_000_000));
+	assert_eq!(parse_price(b"12.34", 0), Some(12));
+	assert_eq!(parse_price(b"", 6), None);
+	assert_eq!(parse_price(b"1.2.3", 6), None);
+	assert_eq!(parse_price(b"-1", 6), None);
+}
 
+#[test]
+fn extract_price_works() {
+	let json = br#"{"data":{"id":"polkadot","priceUsd":"5.2193847362"},"list":[{"p":7}]}"#;
+	assert_eq!(extract_price(json, b"data.priceUsd", 6), Some(5_219_384));
+	assert_eq!(extract_price(json, b"list.0.p", 2), Some(700));
+	assert_eq!(extract_price(br#"{"usd":5.21}"#, b"usd", 6), Some(5_210_000));
+	assert_eq!(extract_price(br#"{"usd":0.000123}"#, b"usd", 6), Some(123));
+	assert_eq!(extract_price(br#"{"usd":-5.21}"#, b"usd", 6), None);
+	assert_eq!(extract_price(json, b"data.id", 6), None);
+	assert_eq!(extract_price(json, b"data.missing", 6), None);
+	assert_eq!(extract_price(b"not json", b"data", 6), None);
+}
+
+#[test]
+fn only_root_manages_feeds() {
+	let (mut t, _, _) = ExternalityBuilder::build();
 	t.execute_with(|| {
-		// Setup
-		let num: u32 = 32;
-		OcwDemo::offchain_signed_tx(num.into()).unwrap();
+		assert_noop!(
+			DotPrice::set_feed(Origin::signed(account(1)), DOT.to_vec(), dot_feed()),
+			BadOrigin
+		);
+		assert_noop!(
+			DotPrice::set_feed(Origin::root(), DOT.to_vec(), FeedConfig { period: 0, ..dot_feed() }),
+			Error::<TestRuntime>::InvalidFeed
+		);
+		assert_noop!(
+			DotPrice::set_feed(Origin::root(), DOT.to_vec(), FeedConfig { decimals: 13, ..dot_feed() }),
+			Error::<TestRuntime>::InvalidFeed
+		);
+		assert_noop!(
+			DotPrice::set_feed(Origin::root(), DOT.to_vec(), FeedConfig { urls: vec![], ..dot_feed() }),
+			Error::<TestRuntime>::InvalidFeed
+		);
+		assert_ok!(DotPrice::set_feed(Origin::root(), DOT.to_vec(), dot_feed()));
+		assert_eq!(DotPrice::feeds(DOT), Some(dot_feed()));
 
-		// Verify
-		let tx = pool_state.write().transactions.pop().unwrap();
-		assert!(pool_state.read().transactions.is_empty());
-		let tx = TestExtrinsic::decode(&mut &*tx).unwrap();
-		assert_eq!(tx.signature.unwrap().0, 0);
-		assert_eq!(tx.call, Call::submit_number_signed(num));
+		assert_noop!(DotPrice::remove_feed(Origin::signed(account(1)), DOT.to_vec()), BadOrigin);
+		assert_ok!(DotPrice::remove_feed(Origin::root(), DOT.to_vec()));
+		assert_eq!(DotPrice::feeds(DOT), None);
+		assert_noop!(
+			DotPrice::remove_feed(Origin::root(), DOT.to_vec()),
+			Error::<TestRuntime>::UnknownFeed
+		);
 	});
 }
 
 #[test]
-fn test_offchain_unsigned_tx() {
-	let (mut t, pool_state, _offchain_state) = ExternalityBuilder::build();
+fn feeds_are_fetched_once_per_period() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		let feed = dot_feed();
+		assert!(DotPrice::feed_is_due(DOT, &feed, 1));
+		// only a successful fetch starts the period
+		assert!(DotPrice::feed_is_due(DOT, &feed, 2));
+		DotPrice::record_fetch(DOT, 1);
+		assert!(!DotPrice::feed_is_due(DOT, &feed, 2));
+		assert!(!DotPrice::feed_is_due(DOT, &feed, 5));
+		assert!(DotPrice::feed_is_due(DOT, &feed, 6));
+		// every symbol keeps its own schedule
+		assert!(DotPrice::feed_is_due(b"KSM", &feed, 6));
+	});
+}
+
+#[test]
+fn offchain_worker_submits_due_feeds() {
+	let (mut t, pool_state, offchain_state) = ExternalityBuilder::build();
+	offchain_state.write().expect_request(testing::PendingRequest {
+		method: "GET".into(),
+		uri: "https://api.coincap.io/v2/assets/polkadot".into(),
+		headers: vec![("User-Agent".into(), HTTP_HEADER_USER_AGENT.into())],
+		response: Some(br#"{"data":{"priceUsd":"5.2193847362"}}"#.to_vec()),
+		sent: true,
+		..Default::default()
+	});
 
 	t.execute_with(|| {
-		// when
-		let num: u32 = 32;
-		OcwDemo::offchain_unsigned_tx(num.into()).unwrap();
-		// then
+		setup_oracles(1);
+		DotPrice::offchain_price_round(1).unwrap();
+
 		let tx = pool_state.write().transactions.pop().unwrap();
 		assert!(pool_state.read().transactions.is_empty());
 		let tx = TestExtrinsic::decode(&mut &*tx).unwrap();
 		assert_eq!(tx.signature, None);
-		assert_eq!(tx.call, Call::submit_number_unsigned(num));
+		if let Call::submit_price_unsigned_with_signed_payload(payload, _) = tx.call {
+			assert_eq!(payload.symbol, DOT.to_vec());
+			assert_eq!(payload.round, 0);
+			assert_eq!(payload.price, 5_219_384);
+		} else {
+			panic!("unexpected call: {:?}", tx.call);
+		}
+
+		// not due again within the feed period, so no request is made
+		DotPrice::offchain_price_round(2).unwrap();
+		assert!(pool_state.read().transactions.is_empty());
+	});
+}
+
+#[test]
+fn failed_fetches_are_retried() {
+	let (mut t, pool_state, offchain_state) = ExternalityBuilder::build();
+	offchain_state.write().expect_request(testing::PendingRequest {
+		method: "GET".into(),
+		uri: "https://api.coincap.io/v2/assets/polkadot".into(),
+		headers: vec![("User-Agent".into(), HTTP_HEADER_USER_AGENT.into())],
+		response: Some(b"not json".to_vec()),
+		sent: true,
+		..Default::default()
+	});
+
+	t.execute_with(|| {
+		setup_oracles(1);
+		DotPrice::offchain_price_round(1).unwrap();
+
+		assert!(pool_state.read().transactions.is_empty());
+		assert!(DotPrice::feed_is_due(DOT, &dot_feed(), 2));
+	});
+}
+
+#[test]
+fn median_works() {
+	assert_eq!(median(&mut []), None);
+	assert_eq!(median(&mut [3, 1, 2]), Some(2));
+	assert_eq!(median(&mut [4, 1, 3, 2]), Some(2));
+	assert_eq!(median(&mut [u64::max_value(), u64::max_value()]), Some(u64::max_value()));
+}
+
+#[test]
+fn only_root_manages_oracles() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		assert_noop!(DotPrice::add_oracle(Origin::signed(account(1)), account(1)), BadOrigin);
+		assert_ok!(DotPrice::add_oracle(Origin::root(), account(1)));
+		assert_noop!(
+			DotPrice::add_oracle(Origin::root(), account(1)),
+			Error::<TestRuntime>::AlreadyOracle
+		);
+		assert_eq!(DotPrice::oracles(), vec![account(1)]);
+
+		assert_noop!(DotPrice::remove_oracle(Origin::signed(account(1)), account(1)), BadOrigin);
+		assert_ok!(DotPrice::remove_oracle(Origin::root(), account(1)));
+		assert_noop!(
+			DotPrice::remove_oracle(Origin::root(), account(1)),
+			Error::<TestRuntime>::NotOracle
+		);
+	});
+}
+
+fn run_to_block(n: u64) {
+	while System::block_number() < n {
+		System::set_block_number(System::block_number() + 1);
+		DotPrice::on_initialize(System::block_number());
+	}
+}
+
+#[test]
+fn round_finalizes_with_median_when_window_closes() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		setup_oracles(4);
+
+		assert_ok!(submit_price(account(1), 0, 5_000_000));
+		assert_eq!(DotPrice::round_start(DOT), Some(1));
+		run_to_block(2);
+		assert_ok!(submit_price(account(2), 0, 5_200_000));
+		assert_ok!(submit_price(account(3), 0, 5_100_000));
+
+		// quorum is reached, but account(4) may still submit until block 6
+		run_to_block(5);
+		assert!(DotPrice::prices(DOT).is_empty());
+		assert_eq!(DotPrice::penalties(account(4)), OraclePenalty::default());
+
+		run_to_block(6);
+		assert_eq!(DotPrice::prices(DOT), vec![5_100_000]);
+		assert_eq!(DotPrice::current_round(DOT), 1);
+		assert!(DotPrice::round_submissions(DOT).is_empty());
+		assert_eq!(DotPrice::round_start(DOT), None);
+		assert!(System::events()
+			.iter()
+			.any(|er| er.event == TestEvent::dot_price(RawEvent::NewPrice(DOT.to_vec(), 0, 5_100_000))));
+
+		// account(4) did not submit before the window closed
+		assert_eq!(DotPrice::penalties(account(4)), OraclePenalty { missed: 1, deviated: 0 });
+		assert_eq!(DotPrice::penalties(account(1)), OraclePenalty::default());
+	});
+}
+
+#[test]
+fn late_submissions_within_the_window_count() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		setup_oracles(4);
+
+		assert_ok!(submit_price(account(1), 0, 5_000_000));
+		assert_ok!(submit_price(account(2), 0, 5_200_000));
+		assert_ok!(submit_price(account(3), 0, 5_100_000));
+		run_to_block(5);
+		assert!(DotPrice::prices(DOT).is_empty());
+
+		// the last eligible oracle closes the round right away
+		assert_ok!(submit_price(account(4), 0, 5_150_000));
+		assert_eq!(DotPrice::prices(DOT), vec![5_125_000]);
+		assert_eq!(DotPrice::round_start(DOT), None);
+		for i in 1..=4 {
+			assert_eq!(DotPrice::penalties(account(i)), OraclePenalty::default());
+		}
+	});
+}
+
+#[test]
+fn rounds_below_quorum_stay_open_after_the_window() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		setup_oracles(4);
+
+		assert_ok!(submit_price(account(1), 0, 5_000_000));
+		assert_ok!(submit_price(account(2), 0, 5_200_000));
+		run_to_block(10);
+		assert!(DotPrice::prices(DOT).is_empty());
+		assert_eq!(DotPrice::penalties(account(3)), OraclePenalty::default());
+
+		// the window is over, so reaching quorum finalizes the round
+		assert_ok!(submit_price(account(3), 0, 5_100_000));
+		assert_eq!(DotPrice::prices(DOT), vec![5_100_000]);
+		assert_eq!(DotPrice::penalties(account(4)), OraclePenalty { missed: 1, deviated: 0 });
+	});
+}
+
+#[test]
+fn outliers_are_rejected_and_penalized() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		setup_oracles(3);
+
+		assert_ok!(submit_price(account(1), 0, 5_000_000));
+		assert_ok!(submit_price(account(2), 0, 9_000_000));
+		assert_ok!(submit_price(account(3), 0, 5_200_000));
+
+		// median of all is 5.2, 9.0 is more than 10% away and dropped
+		assert_eq!(DotPrice::prices(DOT), vec![5_100_000]);
+		assert_eq!(DotPrice::penalties(account(2)), OraclePenalty { missed: 0, deviated: 1 });
+		assert_eq!(DotPrice::penalties(account(1)), OraclePenalty::default());
+	});
+}
+
+#[test]
+fn invalid_price_submissions_are_rejected() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		setup_oracles(3);
+
+		assert_noop!(submit_price(account(9), 0, 1), Error::<TestRuntime>::NotOracle);
+		assert_noop!(submit_price(account(1), 1, 1), Error::<TestRuntime>::WrongRound);
+		assert_ok!(submit_price(account(1), 0, 1));
+		assert_noop!(submit_price(account(1), 0, 2), Error::<TestRuntime>::AlreadySubmitted);
+		assert_noop!(
+			DotPrice::submit_price_unsigned_with_signed_payload(
+				Origin::none(),
+				PricePayload { symbol: b"KSM".to_vec(), round: 0, price: 1, public: account(2) },
+				Default::default(),
+			),
+			Error::<TestRuntime>::UnknownFeed
+		);
+		assert_noop!(
+			DotPrice::submit_price_unsigned_with_signed_payload(
+				Origin::signed(account(1)),
+				PricePayload { symbol: DOT.to_vec(), round: 0, price: 1, public: account(1) },
+				Default::default(),
+			),
+			BadOrigin
+		);
+	});
+}
+
+fn finalize_round_at(block: u64, price: Price) {
+	System::set_block_number(block);
+	let round = DotPrice::current_round(DOT);
+	for i in 1..=Quorum::get() as u8 {
+		assert_ok!(submit_price(account(i), round, price));
+	}
+}
+
+#[test]
+fn twap_weights_prices_by_blocks() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		setup_oracles(3);
+		finalize_round_at(10, 100);
+		finalize_round_at(20, 200);
+		System::set_block_number(30);
+
+		assert_eq!(<DotPrice as PriceProvider<u64>>::latest(DOT), Some(200));
+		assert_eq!(DotPrice::observations(DOT).back().unwrap().cumulative, 1000);
+		// 10 blocks at 100 and 10 blocks at 200
+		assert_eq!(DotPrice::twap(DOT, 20), Some(150));
+		assert_eq!(DotPrice::twap(DOT, 10), Some(200));
+		// 5 blocks at 100 and 10 blocks at 200
+		assert_eq!(DotPrice::twap(DOT, 15), Some(166));
+		assert_eq!(DotPrice::twap(DOT, 0), Some(200));
+		// history starts at block 10
+		assert_eq!(DotPrice::twap(DOT, 25), None);
+		assert_eq!(DotPrice::twap(b"KSM", 10), None);
+	});
+}
+
+#[test]
+fn rounds_in_the_same_block_keep_one_observation() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		setup_oracles(3);
+		finalize_round_at(10, 100);
+		finalize_round_at(10, 300);
+
+		assert_eq!(
+			DotPrice::observations(DOT),
+			vec![Observation { block: 10, price: 300, cumulative: 0 }]
+		);
+		assert_eq!(DotPrice::prices(DOT), vec![100, 300]);
+	});
+}
+
+#[test]
+fn staleness_is_measured_from_last_update() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		setup_oracles(3);
+		assert!(DotPrice::is_stale(DOT, 100));
+
+		finalize_round_at(20, 100);
+		System::set_block_number(30);
+		assert!(!DotPrice::is_stale(DOT, 10));
+		assert!(DotPrice::is_stale(DOT, 9));
+	});
+}
+
+#[test]
+fn quorum_is_capped_by_oracles_holding_a_session_key() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		setup_oracles(3);
+		assert_eq!(DotPrice::quorum(), 3);
+
+		// account(4) holds a session key but is no oracle
+		set_session_authorities(2..=4);
+		assert_eq!(DotPrice::quorum(), 2);
+		assert_ok!(submit_price(account(2), 0, 5_000_000));
+		assert_ok!(submit_price(account(3), 0, 5_200_000));
+		assert_eq!(DotPrice::prices(DOT), vec![5_100_000]);
+
+		set_session_authorities(None.into_iter());
+		assert_eq!(DotPrice::quorum(), 1);
+	});
+}
+
+#[test]
+fn only_session_authorities_send_signed_payloads() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		let public = sp_io::crypto::sr25519_public_keys(KEY_TYPE)[0];
+		assert_ok!(DotPrice::set_feed(Origin::root(), DOT.to_vec(), dot_feed()));
+		assert_ok!(DotPrice::add_oracle(Origin::root(), public));
+
+		let payload = PricePayload { symbol: DOT.to_vec(), round: 0, price: 1, public };
+		let signature = sp_io::crypto::sr25519_sign(KEY_TYPE, &public, &payload.encode()).unwrap();
+		let validate = || <DotPrice as frame_support::unsigned::ValidateUnsigned>::validate_unsigned(
+			TransactionSource::External,
+			&Call::submit_price_unsigned_with_signed_payload(payload.clone(), signature.clone()),
+		);
+
+		assert_eq!(validate(), InvalidTransaction::BadSigner.into());
+
+		SESSION_AUTHORITIES.with(|v| *v.borrow_mut() = vec![public]);
+		assert!(validate().is_ok());
+	});
+}
+
+#[test]
+fn latest_price_and_history_follow_finalized_rounds() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		setup_oracles(3);
+		assert_eq!(DotPrice::latest_price(DOT), None);
+		assert!(DotPrice::price_history(DOT, 5).is_empty());
+
+		finalize_round_at(10, 100);
+		finalize_round_at(20, 200);
+		finalize_round_at(30, 300);
+
+		assert_eq!(DotPrice::latest_price(DOT), Some(FinalizedPrice { round: 2, price: 300, decimals: 6 }));
+		assert_eq!(DotPrice::price_history(DOT, 2), vec![200, 300]);
+		assert_eq!(DotPrice::price_history(DOT, 10), vec![100, 200, 300]);
+		assert!(DotPrice::price_history(DOT, 0).is_empty());
 	});
 }
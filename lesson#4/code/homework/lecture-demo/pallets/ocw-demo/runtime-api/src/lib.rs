@@ -0,0 +1,12 @@
+//! Runtime API definition for pallet-ocw-demo.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use pallet_ocw_demo::NumberStats;
+
+sp_api::decl_runtime_apis! {
+	pub trait OcwDemoApi {
+		/// Statistics of the numbers in the current window.
+		fn number_stats() -> NumberStats;
+	}
+}
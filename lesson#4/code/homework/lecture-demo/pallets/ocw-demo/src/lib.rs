@@ -5,9 +5,12 @@
 #[cfg(test)]
 mod tests;
 
+pub mod ring_buffer;
+
 use core::{convert::TryInto, fmt};
 use frame_support::{
-	debug, decl_error, decl_event, decl_module, decl_storage, dispatch::DispatchResult,
+	debug, decl_error, decl_event, decl_module, decl_storage, dispatch::DispatchResult, ensure,
+	traits::{EnsureOrigin, Get, OneSessionHandler},
 };
 use parity_scale_codec::{Decode, Encode};
 
@@ -20,12 +23,14 @@ use frame_system::{
 };
 use sp_core::crypto::KeyTypeId;
 use sp_runtime::{
-	RuntimeDebug,
+	BoundToRuntimeAppPublic, RuntimeDebug,
 	offchain as rt_offchain,
 	offchain::{
 		storage::StorageValueRef,
 		storage_lock::{StorageLock, BlockAndTime},
 	},
+	traits::{SaturatedConversion, Saturating, Zero},
+	FixedPointNumber, FixedU128,
 	transaction_validity::{
 		InvalidTransaction, TransactionSource, TransactionValidity,
 		ValidTransaction,
@@ -35,7 +40,9 @@ use sp_std::{
 	prelude::*, str,
 	collections::vec_deque::VecDeque,
 };
+use ring_buffer::{RingBuffer, RingMeta};
 
+use ocw_http::{HttpClient, HttpError};
 use serde::{Deserialize, Deserializer};
 
 /// Defines application identifier for crypto keys of this module.
@@ -46,7 +53,12 @@ use serde::{Deserialize, Deserializer};
 /// `KeyTypeId` via the keystore to sign the transaction.
 /// The keys can be inserted manually via RPC (see `author_insertKey`).
 pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"demo");
+/// Default number of recent numbers the statistics are computed over.
 pub const NUM_VEC_LEN: usize = 10;
+/// Upper bound of the window `ManageOrigin` may set with `set_window_size`.
+pub const MAX_WINDOW_SIZE: u32 = 1000;
+/// Number of `GithubInfo` changes kept on-chain.
+pub const GH_HISTORY_LEN: usize = 10;
 /// The type to sign and send transactions.
 pub const UNSIGNED_TXS_PRIORITY: u64 = 100;
 
@@ -58,6 +70,122 @@ pub const FETCH_TIMEOUT_PERIOD: u64 = 3000; // in milli-seconds
 pub const LOCK_TIMEOUT_EXPIRATION: u64 = FETCH_TIMEOUT_PERIOD + 1000; // in milli-seconds
 pub const LOCK_BLOCK_EXPIRATION: u32 = 3; // in block number
 
+/// Running aggregates over the numbers in the window, updated on every append.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct RollingStats {
+	pub count: u32,
+	pub sum: u64,
+	pub sum_of_squares: u128,
+	pub min: u32,
+	pub max: u32,
+}
+
+impl RollingStats {
+	pub fn add(&mut self, number: u32) {
+		if self.count == 0 {
+			self.min = number;
+			self.max = number;
+		} else {
+			self.min = self.min.min(number);
+			self.max = self.max.max(number);
+		}
+		self.count += 1;
+		self.sum = self.sum.saturating_add(number.into());
+		self.sum_of_squares = self.sum_of_squares.saturating_add(u128::from(number).pow(2));
+	}
+
+	/// Take out `number` which left the window. Returns whether `min` or `max` may be wrong
+	///   now and must be recomputed from the window.
+	pub fn remove(&mut self, number: u32) -> bool {
+		self.count = self.count.saturating_sub(1);
+		self.sum = self.sum.saturating_sub(number.into());
+		self.sum_of_squares = self.sum_of_squares.saturating_sub(u128::from(number).pow(2));
+		self.count > 0 && (number == self.min || number == self.max)
+	}
+
+	/// Recompute everything from the numbers in the window.
+	pub fn from_numbers(numbers: &[u32]) -> Self {
+		let mut stats = Self::default();
+		numbers.iter().for_each(|n| stats.add(*n));
+		stats
+	}
+
+	pub fn mean(&self) -> FixedU128 {
+		if self.count == 0 {
+			return FixedU128::zero();
+		}
+		FixedU128::saturating_from_rational(self.sum, self.count)
+	}
+
+	/// The population variance, `E[x^2] - E[x]^2`.
+	pub fn variance(&self) -> FixedU128 {
+		if self.count == 0 {
+			return FixedU128::zero();
+		}
+		let mean = self.mean();
+		FixedU128::saturating_from_rational(self.sum_of_squares, self.count)
+			.saturating_sub(mean.saturating_mul(mean))
+	}
+
+	pub fn summary(&self) -> NumberStats {
+		NumberStats {
+			count: self.count,
+			sum: self.sum,
+			min: self.min,
+			max: self.max,
+			mean: self.mean(),
+			variance: self.variance(),
+		}
+	}
+}
+
+/// Statistics of the numbers in the window, as returned by the runtime API.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct NumberStats {
+	pub count: u32,
+	pub sum: u64,
+	pub min: u32,
+	pub max: u32,
+	pub mean: FixedU128,
+	pub variance: FixedU128,
+}
+
+/// The window of recently submitted numbers.
+type NumberWindow = RingBuffer<u32, NumberRing, NumberSlots>;
+
+/// The jobs the offchain worker can run.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum OcwJob {
+	/// Send a signed transaction with a number.
+	SignedTx,
+	/// Send an unsigned transaction with a number.
+	UnsignedTx,
+	/// Send an unsigned transaction with a signed payload.
+	UnsignedTxSignedPayload,
+	/// Fetch and cache the github info of `substrate-developer-hub`.
+	FetchGithubInfo,
+}
+
+/// How a job is scheduled.
+pub struct JobSpec {
+	pub job: OcwJob,
+	/// Blocks between two runs, unless overridden on-chain by `set_job_period`.
+	pub default_period: u32,
+	/// Jobs due in the same block run from the highest to the lowest priority.
+	pub priority: u8,
+	/// How long the job may hold its lock, in milli-seconds.
+	pub timeout: u64,
+}
+
+/// All jobs of the offchain worker. By default each job runs every 4 blocks, like it used to
+///   when one job was picked per block.
+pub static JOBS: [JobSpec; 4] = [
+	JobSpec { job: OcwJob::SignedTx, default_period: 4, priority: 3, timeout: 1000 },
+	JobSpec { job: OcwJob::UnsignedTx, default_period: 4, priority: 2, timeout: 1000 },
+	JobSpec { job: OcwJob::UnsignedTxSignedPayload, default_period: 4, priority: 1, timeout: 1000 },
+	JobSpec { job: OcwJob::FetchGithubInfo, default_period: 4, priority: 0, timeout: LOCK_TIMEOUT_EXPIRATION },
+];
+
 /// Based on the above `KeyTypeId` we need to generate a pallet-specific crypto type wrapper.
 /// We can utilize the supported crypto kinds (`sr25519`, `ed25519` and `ecdsa`) and augment
 /// them with the pallet-specific identifier.
@@ -91,26 +219,81 @@ pub mod crypto {
 }
 
 #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
-pub struct Payload<Public> {
+pub struct Payload<Public, BlockNumber> {
+	block_number: BlockNumber,
 	number: u32,
 	public: Public
 }
 
-impl <T: SigningTypes> SignedPayload<T> for Payload<T::Public> {
+impl <T: SigningTypes> SignedPayload<T> for Payload<T::Public, T::BlockNumber> {
 	fn public(&self) -> T::Public {
 		self.public.clone()
 	}
 }
 
 // ref: https://serde.rs/container-attrs.html#crate
-#[derive(Deserialize, Encode, Decode, Default)]
-struct GithubInfo {
+#[derive(Deserialize, Encode, Decode, Default, Clone, PartialEq, Eq)]
+pub struct GithubInfo {
 	// Specify our own deserializing function to convert JSON string to vector of bytes
 	#[serde(deserialize_with = "de_string_to_bytes")]
-	login: Vec<u8>,
+	pub login: Vec<u8>,
 	#[serde(deserialize_with = "de_string_to_bytes")]
-	blog: Vec<u8>,
-	public_repos: u32,
+	pub blog: Vec<u8>,
+	pub public_repos: u32,
+}
+
+/// The fields of a `GithubInfo` that changed, `None` for those that did not.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct GithubInfoUpdate {
+	pub login: Option<Vec<u8>>,
+	pub blog: Option<Vec<u8>>,
+	pub public_repos: Option<u32>,
+}
+
+impl GithubInfoUpdate {
+	/// The fields of `new` that differ from `old`. Every field differs from no info at all.
+	pub fn diff(old: Option<&GithubInfo>, new: &GithubInfo) -> Self {
+		fn changed<F: PartialEq>(old_field: Option<F>, new_field: F) -> Option<F> {
+			match old_field {
+				Some(old_field) if old_field == new_field => None,
+				_ => Some(new_field),
+			}
+		}
+		GithubInfoUpdate {
+			login: changed(old.map(|o| o.login.clone()), new.login.clone()),
+			blog: changed(old.map(|o| o.blog.clone()), new.blog.clone()),
+			public_repos: changed(old.map(|o| o.public_repos), new.public_repos),
+		}
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.login.is_none() && self.blog.is_none() && self.public_repos.is_none()
+	}
+
+	/// Overwrite the changed fields of `info`.
+	pub fn apply(&self, info: &mut GithubInfo) {
+		if let Some(login) = &self.login {
+			info.login = login.clone();
+		}
+		if let Some(blog) = &self.blog {
+			info.blog = blog.clone();
+		}
+		if let Some(public_repos) = self.public_repos {
+			info.public_repos = public_repos;
+		}
+	}
+}
+
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct GithubInfoPayload<Public> {
+	pub update: GithubInfoUpdate,
+	pub public: Public,
+}
+
+impl<T: SigningTypes> SignedPayload<T> for GithubInfoPayload<T::Public> {
+	fn public(&self) -> T::Public {
+		self.public.clone()
+	}
 }
 
 pub fn de_string_to_bytes<'de, D>(de: D) -> Result<Vec<u8>, D::Error>
@@ -143,12 +326,34 @@ pub trait Trait: system::Trait + CreateSignedTransaction<Call<Self>> {
 	type Call: From<Call<Self>>;
 	/// The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+	/// Number of blocks after an accepted unsigned number submission during which further
+	///   unsigned number submissions are rejected.
+	type UnsignedInterval: Get<Self::BlockNumber>;
+	/// Whether `submit_number_unsigned`, which carries no signed payload, is accepted at all.
+	type AllowUnsignedWithoutPayload: Get<bool>;
+	/// Origin allowed to change the statistics window and the job periods.
+	type ManageOrigin: EnsureOrigin<Self::Origin>;
 }
 
 decl_storage! {
 	trait Store for Module<T: Trait> as Example {
-		/// A vector of recently submitted numbers. Bounded by NUM_VEC_LEN
-		Numbers get(fn numbers): VecDeque<u32>;
+		/// Offchain worker keys of the current session's validators. Only they can send
+		///   signed payloads.
+		SessionAuthorities get(fn session_authorities): Vec<T::Public>;
+		/// Position and capacity of the window of recently submitted numbers.
+		NumberRing get(fn number_ring): RingMeta =
+			RingMeta { start: 0, len: 0, capacity: NUM_VEC_LEN as u32 };
+		/// The numbers in the window, by slot. See `ring_buffer`.
+		NumberSlots: map hasher(twox_64_concat) u32 => Option<u32>;
+		/// Aggregates over the numbers in the window.
+		Stats get(fn stats): RollingStats;
+		/// The first block at which an unsigned number submission is accepted again.
+		NextUnsignedAt get(fn next_unsigned_at): T::BlockNumber;
+		/// Job periods set by `ManageOrigin`, overriding `JobSpec::default_period`. Zero disables a job.
+		JobPeriods get(fn job_period_override): map hasher(twox_64_concat) OcwJob => Option<T::BlockNumber>;
+		/// Recent changes of the github info with the block they were submitted in, oldest first.
+		///   Bounded by GH_HISTORY_LEN
+		GithubInfoHistory get(fn github_info_history): VecDeque<(T::BlockNumber, GithubInfo)>;
 	}
 }
 
@@ -157,17 +362,21 @@ decl_event!(
 	pub enum Event<T>
 	where
 		AccountId = <T as system::Trait>::AccountId,
+		BlockNumber = <T as system::Trait>::BlockNumber,
 	{
 		/// Event generated when a new number is accepted to contribute to the average.
 		NewNumber(Option<AccountId>, u32),
+		/// The period of a job has been changed.
+		JobPeriodSet(OcwJob, BlockNumber),
+		/// The number of recent numbers the statistics are computed over has been changed.
+		WindowSizeSet(u32),
+		/// The github info changed. Carries the changed fields.
+		GithubInfoUpdated(GithubInfoUpdate),
 	}
 );
 
 decl_error! {
 	pub enum Error for Module<T: Trait> {
-		// Error returned when not sure which ocw function to executed
-		UnknownOffchainMux,
-
 		// Error returned when making signed transactions in off-chain worker
 		NoLocalAcctForSigning,
 		OffchainSignedTxError,
@@ -178,8 +387,23 @@ decl_error! {
 		// Error returned when making unsigned transactions with signed payloads in off-chain worker
 		OffchainUnsignedTxSignedPayloadError,
 
-		// Error returned when fetching github info
+		// Errors returned when fetching github info
 		HttpFetchingError,
+		HttpTimeout,
+		HttpStatusError,
+		HttpResponseTooLarge,
+		HttpParseError,
+		HttpBackingOff,
+
+		// Error returned when the offchain worker would submit an unsigned number before
+		//   `NextUnsignedAt`
+		TooEarlyForUnsigned,
+
+		// Error returned when the window size is zero or above MAX_WINDOW_SIZE
+		InvalidWindowSize,
+
+		// Error returned when a github info update changes nothing on-chain
+		GithubInfoUnchanged,
 	}
 }
 
@@ -198,30 +422,85 @@ decl_module! {
 		}
 
 		#[weight = 10000]
-		pub fn submit_number_unsigned(origin, number: u32) -> DispatchResult {
+		pub fn submit_number_unsigned(origin, _block_number: T::BlockNumber, number: u32)
+			-> DispatchResult
+		{
 			let _ = ensure_none(origin)?;
 			debug::info!("submit_number_unsigned: {}", number);
 			Self::append_or_replace_number(number);
+			Self::close_unsigned_gate();
 
 			Self::deposit_event(RawEvent::NewNumber(None, number));
 			Ok(())
 		}
 
 		#[weight = 10000]
-		pub fn submit_number_unsigned_with_signed_payload(origin, payload: Payload<T::Public>,
-			_signature: T::Signature) -> DispatchResult
+		pub fn submit_number_unsigned_with_signed_payload(origin,
+			payload: Payload<T::Public, T::BlockNumber>, _signature: T::Signature) -> DispatchResult
 		{
 			let _ = ensure_none(origin)?;
 			// we don't need to verify the signature here because it has been verified in
 			//   `validate_unsigned` function when sending out the unsigned tx.
-			let Payload { number, public } = payload;
+			let Payload { number, public, .. } = payload;
 			debug::info!("submit_number_unsigned_with_signed_payload: ({}, {:?})", number, public);
 			Self::append_or_replace_number(number);
+			Self::close_unsigned_gate();
 
 			Self::deposit_event(RawEvent::NewNumber(None, number));
 			Ok(())
 		}
 
+		#[weight = 10000]
+		pub fn submit_github_info_unsigned_with_signed_payload(origin,
+			payload: GithubInfoPayload<T::Public>, _signature: T::Signature) -> DispatchResult
+		{
+			let _ = ensure_none(origin)?;
+			// The signature has been verified in `validate_unsigned`.
+			let GithubInfoPayload { update, public } = payload;
+			debug::info!("submit_github_info_unsigned_with_signed_payload: ({:?}, {:?})", update, public);
+
+			let mut history = Self::github_info_history();
+			let mut info = history.back().map(|(_, info)| info.clone()).unwrap_or_default();
+			update.apply(&mut info);
+			ensure!(
+				history.back().map_or(true, |(_, latest)| *latest != info),
+				Error::<T>::GithubInfoUnchanged
+			);
+
+			if history.len() == GH_HISTORY_LEN {
+				let _ = history.pop_front();
+			}
+			history.push_back((<system::Module<T>>::block_number(), info));
+			GithubInfoHistory::<T>::put(history);
+
+			Self::deposit_event(RawEvent::GithubInfoUpdated(update));
+			Ok(())
+		}
+
+		/// Change the number of recent numbers the statistics are computed over. When the window
+		///   shrinks, the oldest numbers are dropped.
+		#[weight = 10000]
+		pub fn set_window_size(origin, size: u32) -> DispatchResult {
+			T::ManageOrigin::ensure_origin(origin)?;
+			ensure!(size > 0 && size <= MAX_WINDOW_SIZE, Error::<T>::InvalidWindowSize);
+
+			NumberWindow::resize(size);
+			Stats::put(RollingStats::from_numbers(&NumberWindow::items()));
+
+			Self::deposit_event(RawEvent::WindowSizeSet(size));
+			Ok(())
+		}
+
+		/// Change how often the offchain worker runs `job`. A zero period disables it.
+		#[weight = 10000]
+		pub fn set_job_period(origin, job: OcwJob, period: T::BlockNumber) -> DispatchResult {
+			T::ManageOrigin::ensure_origin(origin)?;
+			JobPeriods::<T>::insert(job, period);
+
+			Self::deposit_event(RawEvent::JobPeriodSet(job, period));
+			Ok(())
+		}
+
 		fn offchain_worker(block_number: T::BlockNumber) {
 			debug::info!("Entering off-chain worker");
 
@@ -230,19 +509,8 @@ decl_module! {
 			// 2. Sending unsigned transaction from ocw
 			// 3. Sending unsigned transactions with signed payloads from ocw
 			// 4. Fetching JSON via http requests in ocw
-			const TX_TYPES: u32 = 4;
-			let modu = block_number.try_into().map_or(TX_TYPES, |bn: usize| (bn as u32) % TX_TYPES);
-			let result = match modu {
-				0 => Self::offchain_signed_tx(block_number),
-				1 => Self::offchain_unsigned_tx(block_number),
-				2 => Self::offchain_unsigned_tx_signed_payload(block_number),
-				3 => Self::fetch_github_info(),
-				_ => Err(Error::<T>::UnknownOffchainMux),
-			};
-
-			if let Err(e) = result {
-				debug::error!("offchain_worker error: {:?}", e);
-			}
+			// Each of them is a job in `JOBS`, run when its period has passed.
+			Self::run_due_jobs(block_number);
 		}
 	}
 }
@@ -251,19 +519,102 @@ impl<T: Trait> Module<T> {
 	/// Append a new number to the tail of the list, removing an element from the head if reaching
 	///   the bounded length.
 	fn append_or_replace_number(number: u32) {
-		Numbers::mutate(|numbers| {
-			if numbers.len() == NUM_VEC_LEN {
-				let _ = numbers.pop_front();
+		let evicted = NumberWindow::push(number);
+		Stats::mutate(|stats| {
+			stats.add(number);
+			if let Some(evicted) = evicted {
+				// Finding the new extreme needs the whole window, but only when an extreme left.
+				if stats.remove(evicted) {
+					*stats = RollingStats::from_numbers(&NumberWindow::items());
+				}
 			}
-			numbers.push_back(number);
-			debug::info!("Number vector: {:?}", numbers);
+			debug::info!("Number stats: {:?}", stats);
 		});
 	}
 
-	/// Check if we have fetched github info before. If yes, we can use the cached version
-	///   stored in off-chain worker storage `storage`. If not, we fetch the remote info and
-	///   write the info into the storage for future retrieval.
-	fn fetch_github_info() -> Result<(), Error<T>> {
+	/// The numbers in the window, oldest first.
+	pub fn numbers() -> Vec<u32> {
+		NumberWindow::items()
+	}
+
+	/// Statistics of the numbers in the window.
+	pub fn number_stats() -> NumberStats {
+		Self::stats().summary()
+	}
+
+	/// Reject unsigned number submissions for the next `UnsignedInterval` blocks.
+	fn close_unsigned_gate() {
+		let current_block = <system::Module<T>>::block_number();
+		NextUnsignedAt::<T>::put(current_block.saturating_add(T::UnsignedInterval::get()));
+	}
+
+	/// The period of `job`: the on-chain override if any, else its default.
+	pub fn job_period(spec: &JobSpec) -> T::BlockNumber {
+		Self::job_period_override(spec.job).unwrap_or_else(|| spec.default_period.into())
+	}
+
+	/// Offchain storage key of `job`, e.g. `ocw-demo::job::<job>::last-run`.
+	fn job_key(job: OcwJob, suffix: &[u8]) -> Vec<u8> {
+		[&b"ocw-demo::job::"[..], &job.encode(), b"::", suffix].concat()
+	}
+
+	/// The jobs due at `block_number`, highest priority first. A job is due when it is enabled
+	///   and it did not run on this node within its period.
+	fn due_jobs(block_number: T::BlockNumber) -> Vec<&'static JobSpec> {
+		let mut due = JOBS
+			.iter()
+			.filter(|spec| {
+				let period = Self::job_period(spec);
+				if period.is_zero() {
+					return false;
+				}
+				if spec.job == OcwJob::UnsignedTx && !T::AllowUnsignedWithoutPayload::get() {
+					return false;
+				}
+				let last_run = StorageValueRef::persistent(&Self::job_key(spec.job, b"last-run"))
+					.get::<T::BlockNumber>()
+					.flatten();
+				last_run.map_or(true, |last| block_number >= last.saturating_add(period))
+			})
+			.collect::<Vec<_>>();
+		due.sort_by(|a, b| b.priority.cmp(&a.priority));
+		due
+	}
+
+	/// Run every due job. A job whose lock is still held by an earlier run is skipped, and is
+	///   due again in the next block.
+	fn run_due_jobs(block_number: T::BlockNumber) {
+		for spec in Self::due_jobs(block_number) {
+			let lock_key = Self::job_key(spec.job, b"lock");
+			let mut lock = StorageLock::<BlockAndTime<Self>>::with_block_and_time_deadline(
+				&lock_key, LOCK_BLOCK_EXPIRATION,
+				rt_offchain::Duration::from_millis(spec.timeout)
+			);
+			let _guard = match lock.try_lock() {
+				Ok(guard) => guard,
+				Err(_) => {
+					debug::info!("offchain job {:?} is still running, skipped", spec.job);
+					continue;
+				}
+			};
+
+			StorageValueRef::persistent(&Self::job_key(spec.job, b"last-run")).set(&block_number);
+			let result = match spec.job {
+				OcwJob::SignedTx => Self::offchain_signed_tx(block_number),
+				OcwJob::UnsignedTx => Self::offchain_unsigned_tx(block_number),
+				OcwJob::UnsignedTxSignedPayload => Self::offchain_unsigned_tx_signed_payload(block_number),
+				OcwJob::FetchGithubInfo => Self::fetch_github_info(block_number),
+			};
+			if let Err(e) = result {
+				debug::error!("offchain job {:?} error: {:?}", spec.job, e);
+			}
+		}
+	}
+
+	/// Fetch the github info and compare it with the copy cached in off-chain worker storage
+	///   by the previous run. If some fields changed, submit them on-chain and refresh the
+	///   cached copy.
+	fn fetch_github_info(block_number: T::BlockNumber) -> Result<(), Error<T>> {
 		// Create a reference to Local Storage value.
 		// Since the local storage is common for all offchain workers, it's a good practice
 		// to prepend our entry with the pallet name.
@@ -278,11 +629,6 @@ impl<T: Trait> Module<T> {
 		// the storage comprehensively.
 		//
 		// Ref: https://substrate.dev/rustdocs/v2.0.0/sp_runtime/offchain/storage/struct.StorageValueRef.html
-		if let Some(Some(gh_info)) = s_info.get::<GithubInfo>() {
-			// gh-info has already been fetched. Return early.
-			debug::info!("cached gh-info: {:?}", gh_info);
-			return Ok(());
-		}
 
 		// Since off-chain storage can be accessed by off-chain workers from multiple runs, it is important to lock
 		//   it before doing heavy computations or write operations.
@@ -303,67 +649,78 @@ impl<T: Trait> Module<T> {
 		//   executed by previous run of ocw, so the function just returns.
 		// ref: https://substrate.dev/rustdocs/v2.0.0/sp_runtime/offchain/storage_lock/struct.StorageLock.html#method.try_lock
 		if let Ok(_guard) = lock.try_lock() {
-			match Self::fetch_n_parse() {
-				Ok(gh_info) => { s_info.set(&gh_info); }
-				Err(err) => { return Err(err); }
+			let gh_info = Self::fetch_n_parse(block_number)?;
+			let cached = s_info.get::<GithubInfo>().flatten();
+			let update = GithubInfoUpdate::diff(cached.as_ref(), &gh_info);
+			if update.is_empty() {
+				debug::info!("gh-info unchanged: {:?}", gh_info);
+				return Ok(());
 			}
+
+			Self::offchain_submit_github_info(update)?;
+			// Only cache what has been submitted, so a failed submission is retried next time.
+			s_info.set(&gh_info);
 		}
 		Ok(())
 	}
 
+	/// Submit the changed github info fields as an unsigned transaction with a signed payload.
+	fn offchain_submit_github_info(update: GithubInfoUpdate) -> Result<(), Error<T>> {
+		let signer = Signer::<T, T::AuthorityId>::any_account();
+
+		if let Some((_, res)) = signer.send_unsigned_transaction(
+			|acct| GithubInfoPayload { update: update.clone(), public: acct.public.clone() },
+			Call::submit_github_info_unsigned_with_signed_payload
+		) {
+			return res.map_err(|_| {
+				debug::error!("Failed in offchain_submit_github_info");
+				<Error<T>>::OffchainUnsignedTxSignedPayloadError
+			});
+		}
+
+		debug::error!("No local account available");
+		Err(<Error<T>>::NoLocalAcctForSigning)
+	}
+
 	/// Fetch from remote and deserialize the JSON to a struct
-	fn fetch_n_parse() -> Result<GithubInfo, Error<T>> {
-		let resp_bytes = Self::fetch_from_remote().map_err(|e| {
-			debug::error!("fetch_from_remote error: {:?}", e);
-			<Error<T>>::HttpFetchingError
-		})?;
+	fn fetch_n_parse(block_number: T::BlockNumber) -> Result<GithubInfo, Error<T>> {
+		let resp_bytes = Self::fetch_from_remote(block_number)?;
 
-		let resp_str = str::from_utf8(&resp_bytes).map_err(|_| <Error<T>>::HttpFetchingError)?;
+		let resp_str = str::from_utf8(&resp_bytes).map_err(|_| <Error<T>>::HttpParseError)?;
 		// Print out our fetched JSON string
 		debug::info!("{}", resp_str);
 
 		// Deserializing JSON to struct, thanks to `serde` and `serde_derive`
 		let gh_info: GithubInfo =
-			serde_json::from_str(&resp_str).map_err(|_| <Error<T>>::HttpFetchingError)?;
+			serde_json::from_str(&resp_str).map_err(|_| <Error<T>>::HttpParseError)?;
 		Ok(gh_info)
 	}
 
-	/// This function uses the `offchain::http` API to query the remote github information,
+	/// This function uses the shared `ocw_http` client to query the remote github information,
 	///   and returns the JSON response as vector of bytes.
-	fn fetch_from_remote() -> Result<Vec<u8>, Error<T>> {
+	fn fetch_from_remote(block_number: T::BlockNumber) -> Result<Vec<u8>, Error<T>> {
 		debug::info!("sending request to: {}", HTTP_REMOTE_REQUEST);
 
-		// Initiate an external HTTP GET request. This is using high-level wrappers from `sp_runtime`.
-		let request = rt_offchain::http::Request::get(HTTP_REMOTE_REQUEST);
-
-		// Keeping the offchain worker execution time reasonable, so limiting the call to be within 3s.
-		let timeout = sp_io::offchain::timestamp()
-			.add(rt_offchain::Duration::from_millis(FETCH_TIMEOUT_PERIOD));
-
 		// For github API request, we also need to specify `user-agent` in http request header.
 		//   See: https://developer.github.com/v3/#user-agent-required
-		let pending = request
+		HttpClient::new(b"offchain-demo::gh-info")
+			.add_url(HTTP_REMOTE_REQUEST)
 			.add_header("User-Agent", HTTP_HEADER_USER_AGENT)
-			.deadline(timeout) // Setting the timeout time
-			.send() // Sending the request out by the host
-			.map_err(|_| <Error<T>>::HttpFetchingError)?;
-
-		// By default, the http request is async from the runtime perspective. So we are asking the
-		//   runtime to wait here.
-		// The returning value here is a `Result` of `Result`, so we are unwrapping it twice by two `?`
-		//   ref: https://substrate.dev/rustdocs/v2.0.0/sp_runtime/offchain/http/struct.PendingRequest.html#method.try_wait
-		let response = pending
-			.try_wait(timeout)
-			.map_err(|_| <Error<T>>::HttpFetchingError)?
-			.map_err(|_| <Error<T>>::HttpFetchingError)?;
-
-		if response.code != 200 {
-			debug::error!("Unexpected http request status code: {}", response.code);
-			return Err(<Error<T>>::HttpFetchingError);
-		}
+			.timeout(FETCH_TIMEOUT_PERIOD)
+			.fetch(block_number)
+			.map_err(Self::http_error)
+	}
 
-		// Next we fully read the response body and collect it to a vector of bytes.
-		Ok(response.body().collect::<Vec<u8>>())
+	/// Map a failed fetch to the error of this pallet.
+	fn http_error(e: HttpError) -> Error<T> {
+		debug::error!("http fetching error: {:?}", e);
+		match e {
+			HttpError::Unreachable => <Error<T>>::HttpFetchingError,
+			HttpError::Timeout => <Error<T>>::HttpTimeout,
+			HttpError::Status(_) => <Error<T>>::HttpStatusError,
+			HttpError::TooLarge => <Error<T>>::HttpResponseTooLarge,
+			HttpError::BackingOff => <Error<T>>::HttpBackingOff,
+		}
 	}
 
 	fn offchain_signed_tx(block_number: T::BlockNumber) -> Result<(), Error<T>> {
@@ -401,8 +758,13 @@ impl<T: Trait> Module<T> {
 	}
 
 	fn offchain_unsigned_tx(block_number: T::BlockNumber) -> Result<(), Error<T>> {
+		// The transaction would be rejected by `validate_unsigned` anyway, so don't bother.
+		if Self::next_unsigned_at() > block_number {
+			return Err(<Error<T>>::TooEarlyForUnsigned);
+		}
+
 		let number: u32 = block_number.try_into().unwrap_or(0) as u32;
-		let call = Call::submit_number_unsigned(number);
+		let call = Call::submit_number_unsigned(block_number, number);
 
 		// `submit_unsigned_transaction` returns a type of `Result<(), ()>`
 		//   ref: https://substrate.dev/rustdocs/v2.0.0/frame_system/offchain/struct.SubmitTransaction.html#method.submit_unsigned_transaction
@@ -414,6 +776,10 @@ impl<T: Trait> Module<T> {
 	}
 
 	fn offchain_unsigned_tx_signed_payload(block_number: T::BlockNumber) -> Result<(), Error<T>> {
+		if Self::next_unsigned_at() > block_number {
+			return Err(<Error<T>>::TooEarlyForUnsigned);
+		}
+
 		// Retrieve the signer to sign the payload
 		let signer = Signer::<T, T::AuthorityId>::any_account();
 
@@ -425,7 +791,7 @@ impl<T: Trait> Module<T> {
 		//   - `Some((account, Ok(())))`: transaction is successfully sent
 		//   - `Some((account, Err(())))`: error occured when sending the transaction
 		if let Some((_, res)) = signer.send_unsigned_transaction(
-			|acct| Payload { number, public: acct.public.clone() },
+			|acct| Payload { block_number, number, public: acct.public.clone() },
 			Call::submit_number_unsigned_with_signed_payload
 		) {
 			return res.map_err(|_| {
@@ -452,18 +818,108 @@ impl<T: Trait> frame_support::unsigned::ValidateUnsigned for Module<T> {
 			.build();
 
 		match call {
-			Call::submit_number_unsigned(_number) => valid_tx(b"submit_number_unsigned".to_vec()),
+			Call::submit_number_unsigned(block_number, number) => {
+				if !T::AllowUnsignedWithoutPayload::get() {
+					return InvalidTransaction::Call.into();
+				}
+				Self::validate_number_submission(block_number, *number)
+			},
 			Call::submit_number_unsigned_with_signed_payload(ref payload, ref signature) => {
 				if !SignedPayload::<T>::verify::<T::AuthorityId>(payload, signature.clone()) {
 					return InvalidTransaction::BadProof.into();
 				}
-				valid_tx(b"submit_number_unsigned_with_signed_payload".to_vec())
+				if !Self::is_session_authority(&payload.public) {
+					return InvalidTransaction::BadSigner.into();
+				}
+				Self::validate_number_submission(&payload.block_number, payload.number)
+			},
+			Call::submit_github_info_unsigned_with_signed_payload(ref payload, ref signature) => {
+				if !SignedPayload::<T>::verify::<T::AuthorityId>(payload, signature.clone()) {
+					return InvalidTransaction::BadProof.into();
+				}
+				if !Self::is_session_authority(&payload.public) {
+					return InvalidTransaction::BadSigner.into();
+				}
+				// The same update is only accepted once, whichever node submits it.
+				valid_tx([&b"submit_github_info"[..], &payload.update.encode()].concat())
 			},
 			_ => InvalidTransaction::Call.into(),
 		}
 	}
 }
 
+impl<T: Trait> BoundToRuntimeAppPublic for Module<T> {
+	type Public = crypto::Public;
+}
+
+/// Keeps `SessionAuthorities` in step with the session keys registered under `KEY_TYPE`.
+impl<T: Trait> OneSessionHandler<T::AccountId> for Module<T>
+where
+	T::Public: From<sp_core::sr25519::Public>,
+{
+	type Key = crypto::Public;
+
+	fn on_genesis_session<'a, I: 'a>(validators: I)
+	where
+		I: Iterator<Item = (&'a T::AccountId, Self::Key)>,
+	{
+		Self::set_session_authorities(validators);
+	}
+
+	fn on_new_session<'a, I: 'a>(changed: bool, validators: I, _queued_validators: I)
+	where
+		I: Iterator<Item = (&'a T::AccountId, Self::Key)>,
+	{
+		if changed {
+			Self::set_session_authorities(validators);
+		}
+	}
+
+	fn on_disabled(_validator_index: usize) {}
+}
+
+impl<T: Trait> Module<T> {
+	fn set_session_authorities<'a, I: 'a>(validators: I)
+	where
+		I: Iterator<Item = (&'a T::AccountId, crypto::Public)>,
+		T::Public: From<sp_core::sr25519::Public>,
+	{
+		let keys = validators
+			.map(|(_, key)| sp_core::sr25519::Public::from(key).into())
+			.collect::<Vec<T::Public>>();
+		SessionAuthorities::<T>::put(keys);
+	}
+
+	/// Whether `public` is the offchain worker key of a current session validator.
+	pub fn is_session_authority(public: &T::Public) -> bool {
+		Self::session_authorities().contains(public)
+	}
+
+	/// Checks shared by the unsigned number submissions. A submission made for `block_number`
+	///   is only valid from `NextUnsignedAt` on and not before its own block. The longer the
+	///   gate has been open, the staler the numbers are, so the higher the priority.
+	fn validate_number_submission(block_number: &T::BlockNumber, number: u32) -> TransactionValidity {
+		let next_unsigned_at = Self::next_unsigned_at();
+		if &next_unsigned_at > block_number {
+			return InvalidTransaction::Stale.into();
+		}
+		let current_block = <system::Module<T>>::block_number();
+		if &current_block < block_number {
+			return InvalidTransaction::Future.into();
+		}
+
+		let staleness: u64 = current_block.saturating_sub(next_unsigned_at).saturated_into();
+		ValidTransaction::with_tag_prefix("ocw-demo")
+			.priority(UNSIGNED_TXS_PRIORITY.saturating_mul(staleness.saturating_add(1)))
+			// One submission per block and number, whichever way it is sent.
+			.and_provides((&b"submit_number"[..], block_number, number).encode())
+			// The submission is pointless once the next one is allowed.
+			.longevity(T::UnsignedInterval::get().saturated_into::<u64>().max(1))
+			.propagate(true)
+			.build()
+	}
+}
+
 impl<T: Trait> rt_offchain::storage_lock::BlockNumberProvider for Module<T> {
 	type BlockNumber = T::BlockNumber;
 	fn current_block_number() -> Self::BlockNumber {
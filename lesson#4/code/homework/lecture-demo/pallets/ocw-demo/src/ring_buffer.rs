@@ -0,0 +1,84 @@
+//! A bounded FIFO queue kept in runtime storage.
+//!
+//! Items live in a `StorageMap` keyed by their slot, and a `StorageValue` of `RingMeta` tracks
+//!   where the oldest item is. Appending touches one slot and the meta, whatever the capacity.
+
+use frame_support::storage::{StorageMap, StorageValue};
+use parity_scale_codec::{Decode, Encode, FullCodec};
+use sp_runtime::RuntimeDebug;
+use sp_std::{marker::PhantomData, prelude::*};
+
+/// Position and bound of a ring buffer.
+#[derive(Encode, Decode, Default, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub struct RingMeta {
+	/// Slot of the oldest item.
+	pub start: u32,
+	/// Number of items stored.
+	pub len: u32,
+	/// Maximum number of items. Nothing is stored while it is zero.
+	pub capacity: u32,
+}
+
+/// A ring buffer over the storage items `Meta` and `Items`, e.g.
+///   `RingBuffer<u32, NumberRing, NumberSlots>`.
+pub struct RingBuffer<Item, Meta, Items>(PhantomData<(Item, Meta, Items)>);
+
+impl<Item, Meta, Items> RingBuffer<Item, Meta, Items>
+where
+	Item: FullCodec,
+	Meta: StorageValue<RingMeta, Query = RingMeta>,
+	Items: StorageMap<u32, Item, Query = Option<Item>>,
+{
+	pub fn meta() -> RingMeta {
+		Meta::get()
+	}
+
+	/// Append `item` as the newest item. When the buffer is full the oldest item is evicted
+	///   and returned.
+	pub fn push(item: Item) -> Option<Item> {
+		let mut meta = Meta::get();
+		if meta.capacity == 0 {
+			return Some(item);
+		}
+
+		let evicted = if meta.len < meta.capacity {
+			Items::insert((meta.start + meta.len) % meta.capacity, item);
+			meta.len += 1;
+			None
+		} else {
+			let evicted = Items::take(meta.start);
+			Items::insert(meta.start, item);
+			meta.start = (meta.start + 1) % meta.capacity;
+			evicted
+		};
+		Meta::put(meta);
+		evicted
+	}
+
+	/// All items, oldest first.
+	pub fn items() -> Vec<Item> {
+		let meta = Meta::get();
+		(0..meta.len)
+			.filter_map(|i| Items::get((meta.start + i) % meta.capacity))
+			.collect()
+	}
+
+	/// Change the capacity, keeping the newest items that fit. Returns the evicted items,
+	///   oldest first. This rewrites every item, so it is meant for rare admin changes.
+	pub fn resize(capacity: u32) -> Vec<Item> {
+		let mut items = Self::items();
+		let meta = Meta::get();
+		for slot in 0..meta.capacity {
+			Items::remove(slot);
+		}
+
+		let overflow = items.len().saturating_sub(capacity as usize);
+		let kept = items.split_off(overflow);
+		let len = kept.len() as u32;
+		for (slot, item) in kept.into_iter().enumerate() {
+			Items::insert(slot as u32, item);
+		}
+		Meta::put(RingMeta { start: 0, len, capacity });
+		items
+	}
+}
@@ -1,5 +1,8 @@
 use crate::*;
-use frame_support::{assert_ok, impl_outer_event, impl_outer_origin, parameter_types};
+use frame_support::{
+	assert_noop, assert_ok, impl_outer_event, impl_outer_origin, parameter_types, traits::Get,
+};
+use frame_system::EnsureRoot;
 use parity_scale_codec::{alloc::sync::Arc, Decode};
 use parking_lot::RwLock;
 use sp_core::{
@@ -13,9 +16,10 @@ use sp_core::{
 	H256,
 };
 use sp_io::TestExternalities;
+use std::cell::RefCell;
 use sp_runtime::{
 	testing::{Header, TestXt},
-	traits::{BlakeTwo256, IdentityLookup, Verify},
+	traits::{BadOrigin, BlakeTwo256, IdentityLookup, Verify},
 	Perbill,
 };
 
@@ -77,12 +81,27 @@ type TestExtrinsic = TestXt<Call<TestRuntime>, ()>;
 
 parameter_types! {
 	pub const UnsignedPriority: u64 = 100;
+	pub const UnsignedInterval: u64 = 2;
+}
+
+thread_local! {
+	static ALLOW_UNSIGNED: RefCell<bool> = RefCell::new(true);
+}
+
+pub struct AllowUnsignedWithoutPayload;
+impl Get<bool> for AllowUnsignedWithoutPayload {
+	fn get() -> bool {
+		ALLOW_UNSIGNED.with(|v| *v.borrow())
+	}
 }
 
 impl Trait for TestRuntime {
 	type AuthorityId = crypto::TestAuthId;
 	type Call = Call<TestRuntime>;
 	type Event = TestEvent;
+	type UnsignedInterval = UnsignedInterval;
+	type AllowUnsignedWithoutPayload = AllowUnsignedWithoutPayload;
+	type ManageOrigin = EnsureRoot<Self::AccountId>;
 }
 
 impl<LocalCall> system::offchain::CreateSignedTransaction<LocalCall> for TestRuntime
@@ -161,8 +180,8 @@ fn submit_number_signed_works() {
 			Origin::signed(acct),
 			num
 		));
-		// A number is inserted to <Numbers> vec
-		assert_eq!(<Numbers>::get(), vec![num]);
+		// A number is inserted to the window
+		assert_eq!(OcwDemo::numbers(), vec![num]);
 		// An event is emitted
 		assert!(System::events()
 			.iter()
@@ -174,8 +193,8 @@ fn submit_number_signed_works() {
 			Origin::signed(acct),
 			num2
 		));
-		// A number is inserted to <Numbers> vec
-		assert_eq!(<Numbers>::get(), vec![num, num2]);
+		// A number is inserted to the window
+		assert_eq!(OcwDemo::numbers(), vec![num, num2]);
 	});
 }
 
@@ -210,6 +229,366 @@ fn test_offchain_unsigned_tx() {
 		assert!(pool_state.read().transactions.is_empty());
 		let tx = TestExtrinsic::decode(&mut &*tx).unwrap();
 		assert_eq!(tx.signature, None);
-		assert_eq!(tx.call, Call::submit_number_unsigned(num));
+		assert_eq!(tx.call, Call::submit_number_unsigned(num.into(), num));
+	});
+}
+
+fn due(block_number: u64) -> Vec<OcwJob> {
+	OcwDemo::due_jobs(block_number).iter().map(|spec| spec.job).collect()
+}
+
+fn disable_job(job: OcwJob) {
+	assert_ok!(OcwDemo::set_job_period(Origin::root(), job, 0));
+}
+
+#[test]
+fn job_periods_are_set_by_root() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		assert_noop!(
+			OcwDemo::set_job_period(Origin::signed(Default::default()), OcwJob::UnsignedTx, 2),
+			BadOrigin
+		);
+		assert_eq!(OcwDemo::job_period(&JOBS[1]), 4);
+
+		assert_ok!(OcwDemo::set_job_period(Origin::root(), OcwJob::UnsignedTx, 2));
+		assert_eq!(OcwDemo::job_period(&JOBS[1]), 2);
+		assert!(System::events().iter().any(|er| er.event
+			== TestEvent::ocw_demo(RawEvent::JobPeriodSet(OcwJob::UnsignedTx, 2))));
+
+		disable_job(OcwJob::UnsignedTx);
+		assert!(!due(1).contains(&OcwJob::UnsignedTx));
+	});
+}
+
+#[test]
+fn due_jobs_run_by_priority_once_per_period() {
+	let (mut t, pool_state, _offchain_state) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		// fetching github info would send an http request
+		disable_job(OcwJob::FetchGithubInfo);
+		assert_eq!(
+			due(1),
+			vec![OcwJob::SignedTx, OcwJob::UnsignedTx, OcwJob::UnsignedTxSignedPayload]
+		);
+
+		OcwDemo::run_due_jobs(1);
+		assert_eq!(pool_state.read().transactions.len(), 3);
+		assert!(due(4).is_empty());
+
+		assert_ok!(OcwDemo::set_job_period(Origin::root(), OcwJob::UnsignedTx, 2));
+		assert_eq!(due(3), vec![OcwJob::UnsignedTx]);
+		assert_eq!(due(5).len(), 3);
+	});
+}
+
+#[test]
+fn jobs_with_a_held_lock_are_skipped() {
+	let (mut t, pool_state, _offchain_state) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		disable_job(OcwJob::SignedTx);
+		disable_job(OcwJob::UnsignedTxSignedPayload);
+		disable_job(OcwJob::FetchGithubInfo);
+
+		let lock_key = OcwDemo::job_key(OcwJob::UnsignedTx, b"lock");
+		let mut lock = StorageLock::<BlockAndTime<OcwDemo>>::with_block_and_time_deadline(
+			&lock_key, LOCK_BLOCK_EXPIRATION,
+			rt_offchain::Duration::from_millis(1000)
+		);
+		let guard = lock.try_lock().unwrap();
+		OcwDemo::run_due_jobs(1);
+		assert!(pool_state.read().transactions.is_empty());
+		// the job did not run, so it is still due
+		assert_eq!(due(1), vec![OcwJob::UnsignedTx]);
+
+		drop(guard);
+		OcwDemo::run_due_jobs(1);
+		let tx = pool_state.write().transactions.pop().unwrap();
+		let tx = TestExtrinsic::decode(&mut &*tx).unwrap();
+		assert_eq!(tx.call, Call::submit_number_unsigned(1, 1));
+	});
+}
+
+fn gh_info(public_repos: u32) -> GithubInfo {
+	GithubInfo {
+		login: b"substrate-developer-hub".to_vec(),
+		blog: b"https://substrate.dev".to_vec(),
+		public_repos,
+	}
+}
+
+fn submit_github_info(update: GithubInfoUpdate) -> DispatchResult {
+	OcwDemo::submit_github_info_unsigned_with_signed_payload(
+		Origin::none(),
+		GithubInfoPayload { update, public: Default::default() },
+		Default::default(),
+	)
+}
+
+fn expect_github_request(offchain_state: &Arc<RwLock<OffchainState>>, public_repos: u32) {
+	let body = format!(
+		r#"{{"login":"substrate-developer-hub","blog":"https://substrate.dev","public_repos":{},"followers":0}}"#,
+		public_repos
+	);
+	offchain_state.write().expect_request(testing::PendingRequest {
+		method: "GET".into(),
+		uri: HTTP_REMOTE_REQUEST.into(),
+		headers: vec![("User-Agent".into(), HTTP_HEADER_USER_AGENT.into())],
+		response: Some(body.into_bytes()),
+		sent: true,
+		..Default::default()
+	});
+}
+
+#[test]
+fn github_info_update_has_changed_fields_only() {
+	let full = GithubInfoUpdate::diff(None, &gh_info(50));
+	assert_eq!(full.login, Some(b"substrate-developer-hub".to_vec()));
+	assert_eq!(full.public_repos, Some(50));
+	assert!(GithubInfoUpdate::diff(Some(&gh_info(50)), &gh_info(50)).is_empty());
+
+	let update = GithubInfoUpdate::diff(Some(&gh_info(50)), &gh_info(51));
+	assert_eq!(update, GithubInfoUpdate { public_repos: Some(51), ..Default::default() });
+
+	let mut info = gh_info(50);
+	update.apply(&mut info);
+	assert_eq!(info, gh_info(51));
+}
+
+#[test]
+fn github_info_changes_are_kept_with_their_block() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		assert_ok!(submit_github_info(GithubInfoUpdate::diff(None, &gh_info(50))));
+		assert_noop!(
+			submit_github_info(GithubInfoUpdate { public_repos: Some(50), ..Default::default() }),
+			Error::<TestRuntime>::GithubInfoUnchanged
+		);
+
+		System::set_block_number(2);
+		let update = GithubInfoUpdate { public_repos: Some(51), ..Default::default() };
+		assert_ok!(submit_github_info(update.clone()));
+		assert_eq!(
+			OcwDemo::github_info_history(),
+			vec![(1, gh_info(50)), (2, gh_info(51))]
+		);
+		assert!(System::events()
+			.iter()
+			.any(|er| er.event == TestEvent::ocw_demo(RawEvent::GithubInfoUpdated(update.clone()))));
+
+		for n in 0..GH_HISTORY_LEN as u32 {
+			assert_ok!(submit_github_info(GithubInfoUpdate {
+				public_repos: Some(100 + n),
+				..Default::default()
+			}));
+		}
+		assert_eq!(OcwDemo::github_info_history().len(), GH_HISTORY_LEN);
+	});
+}
+
+#[test]
+fn offchain_worker_submits_changed_github_info() {
+	let (mut t, pool_state, offchain_state) = ExternalityBuilder::build();
+	expect_github_request(&offchain_state, 50);
+	expect_github_request(&offchain_state, 50);
+	expect_github_request(&offchain_state, 51);
+
+	t.execute_with(|| {
+		let submitted = || {
+			let tx = pool_state.write().transactions.pop().unwrap();
+			assert!(pool_state.read().transactions.is_empty());
+			match TestExtrinsic::decode(&mut &*tx).unwrap().call {
+				Call::submit_github_info_unsigned_with_signed_payload(payload, _) => payload.update,
+				call => panic!("unexpected call: {:?}", call),
+			}
+		};
+
+		// nothing is cached yet, so every field is submitted
+		OcwDemo::fetch_github_info(1).unwrap();
+		assert_eq!(submitted(), GithubInfoUpdate::diff(None, &gh_info(50)));
+
+		// unchanged info is not submitted again
+		OcwDemo::fetch_github_info(2).unwrap();
+		assert!(pool_state.read().transactions.is_empty());
+
+		OcwDemo::fetch_github_info(3).unwrap();
+		assert_eq!(submitted(), GithubInfoUpdate { public_repos: Some(51), ..Default::default() });
+	});
+}
+
+fn validate(call: Call<TestRuntime>) -> TransactionValidity {
+	<OcwDemo as frame_support::unsigned::ValidateUnsigned>::validate_unsigned(
+		TransactionSource::External,
+		&call,
+	)
+}
+
+#[test]
+fn unsigned_numbers_are_gated_per_interval() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		assert!(validate(Call::submit_number_unsigned(1, 7)).is_ok());
+		// not for a block that has not been reached yet
+		assert_eq!(
+			validate(Call::submit_number_unsigned(2, 7)),
+			InvalidTransaction::Future.into()
+		);
+
+		assert_ok!(OcwDemo::submit_number_unsigned(Origin::none(), 1, 7));
+		assert_eq!(OcwDemo::next_unsigned_at(), 3);
+
+		System::set_block_number(2);
+		assert_eq!(
+			validate(Call::submit_number_unsigned(2, 8)),
+			InvalidTransaction::Stale.into()
+		);
+		assert_eq!(OcwDemo::offchain_unsigned_tx(2), Err(Error::<TestRuntime>::TooEarlyForUnsigned));
+
+		System::set_block_number(3);
+		assert!(validate(Call::submit_number_unsigned(3, 8)).is_ok());
+	});
+}
+
+#[test]
+fn unsigned_number_tags_and_priority() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		let tx = validate(Call::submit_number_unsigned(1, 7)).unwrap();
+		let other = validate(Call::submit_number_unsigned(1, 8)).unwrap();
+		assert_ne!(tx.provides, other.provides);
+		assert_eq!(tx.longevity, 2);
+		assert_eq!(tx.priority, UNSIGNED_TXS_PRIORITY * 2);
+
+		// the longer nothing has been accepted, the higher the priority
+		System::set_block_number(10);
+		let stale = validate(Call::submit_number_unsigned(10, 7)).unwrap();
+		assert_eq!(stale.priority, UNSIGNED_TXS_PRIORITY * 11);
+	});
+}
+
+#[test]
+fn unsigned_numbers_without_payload_can_be_disallowed() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		ALLOW_UNSIGNED.with(|v| *v.borrow_mut() = false);
+		assert_eq!(validate(Call::submit_number_unsigned(1, 7)), InvalidTransaction::Call.into());
+		assert!(!due(1).contains(&OcwJob::UnsignedTx));
+		ALLOW_UNSIGNED.with(|v| *v.borrow_mut() = true);
+	});
+}
+
+#[test]
+fn signed_number_payloads_are_verified() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		let payload = Payload { block_number: 1, number: 7, public: Default::default() };
+		assert_eq!(
+			validate(Call::submit_number_unsigned_with_signed_payload(payload, Default::default())),
+			InvalidTransaction::BadProof.into()
+		);
+	});
+}
+
+fn submit_numbers(numbers: &[u32]) {
+	for number in numbers {
+		assert_ok!(OcwDemo::submit_number_signed(Origin::signed(Default::default()), *number));
+	}
+}
+
+#[test]
+fn window_keeps_the_latest_numbers() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		let numbers = (1..=NUM_VEC_LEN as u32 + 3).collect::<Vec<_>>();
+		submit_numbers(&numbers);
+		assert_eq!(OcwDemo::numbers(), numbers[3..].to_vec());
+		assert_eq!(OcwDemo::number_ring().len, NUM_VEC_LEN as u32);
+	});
+}
+
+#[test]
+fn stats_follow_the_window() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		assert_eq!(OcwDemo::number_stats(), NumberStats::default());
+		assert_ok!(OcwDemo::set_window_size(Origin::root(), 3));
+
+		submit_numbers(&[2, 4, 4]);
+		let stats = OcwDemo::number_stats();
+		assert_eq!((stats.count, stats.sum, stats.min, stats.max), (3, 10, 2, 4));
+		assert_eq!(stats.mean, FixedU128::saturating_from_rational(10, 3));
+
+		// 2 leaves the window, so the minimum is recomputed
+		submit_numbers(&[6]);
+		let stats = OcwDemo::number_stats();
+		assert_eq!((stats.count, stats.sum, stats.min, stats.max), (3, 14, 4, 6));
+
+		submit_numbers(&[5, 5]);
+		let stats = OcwDemo::number_stats();
+		assert_eq!((stats.min, stats.max), (5, 6));
+		assert_eq!(stats.mean, FixedU128::saturating_from_rational(16, 3));
+		// ((6-16/3)^2 + 2 * (5-16/3)^2) / 3 = 2/9, up to rounding
+		let expected = FixedU128::saturating_from_rational(2, 9);
+		let error = stats.variance.saturating_sub(expected).max(expected.saturating_sub(stats.variance));
+		assert!(error < FixedU128::saturating_from_rational(1, 1_000_000_000));
+	});
+}
+
+#[test]
+fn window_size_is_set_by_root() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		assert_noop!(OcwDemo::set_window_size(Origin::signed(Default::default()), 3), BadOrigin);
+		assert_noop!(
+			OcwDemo::set_window_size(Origin::root(), 0),
+			Error::<TestRuntime>::InvalidWindowSize
+		);
+		assert_noop!(
+			OcwDemo::set_window_size(Origin::root(), MAX_WINDOW_SIZE + 1),
+			Error::<TestRuntime>::InvalidWindowSize
+		);
+
+		submit_numbers(&[1, 2, 3, 4, 5]);
+		// shrinking drops the oldest numbers and recomputes the stats
+		assert_ok!(OcwDemo::set_window_size(Origin::root(), 2));
+		assert_eq!(OcwDemo::numbers(), vec![4, 5]);
+		assert_eq!(OcwDemo::number_stats().sum, 9);
+		assert!(System::events()
+			.iter()
+			.any(|er| er.event == TestEvent::ocw_demo(RawEvent::WindowSizeSet(2))));
+
+		// growing keeps everything
+		assert_ok!(OcwDemo::set_window_size(Origin::root(), 4));
+		submit_numbers(&[6, 7, 8]);
+		assert_eq!(OcwDemo::numbers(), vec![5, 6, 7, 8]);
+		assert_eq!(OcwDemo::number_stats().min, 5);
+	});
+}
+
+#[test]
+fn only_session_authorities_send_signed_payloads() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		let public = sp_io::crypto::sr25519_public_keys(KEY_TYPE)[0];
+		let payload = Payload { block_number: 1, number: 7, public };
+		let signature = sp_io::crypto::sr25519_sign(KEY_TYPE, &public, &payload.encode()).unwrap();
+		let call = Call::submit_number_unsigned_with_signed_payload(payload, signature);
+
+		assert_eq!(validate(call.clone()), InvalidTransaction::BadSigner.into());
+
+		let key = crypto::Public::from(public);
+		<OcwDemo as OneSessionHandler<sr25519::Public>>::on_genesis_session(
+			vec![(&public, key)].into_iter(),
+		);
+		assert_eq!(OcwDemo::session_authorities(), vec![public]);
+		assert!(validate(call).is_ok());
+
+		// a session without changes keeps the authorities
+		<OcwDemo as OneSessionHandler<sr25519::Public>>::on_new_session(
+			false,
+			Vec::new().into_iter(),
+			Vec::new().into_iter(),
+		);
+		assert_eq!(OcwDemo::session_authorities(), vec![public]);
 	});
 }
@@ -0,0 +1,211 @@
+//! A small HTTP client for offchain workers, shared by `pallet-ocw-demo` and `pallet-dot-price`.
+//!
+//! On top of the `sp_runtime::offchain::http` wrappers it adds:
+//!   - a list of fallback URLs that are tried in order until one answers with `200`,
+//!   - exponential backoff in blocks after every fetch where all URLs failed. The backoff state
+//!     lives in offchain persistent storage so it survives across offchain worker runs,
+//!   - a bound on the response size,
+//!   - distinct error variants so callers can tell a timeout from a bad status or a bad body.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(test)]
+mod tests;
+
+use parity_scale_codec::{Codec, Decode, Encode};
+use sp_runtime::{
+	offchain as rt_offchain,
+	offchain::storage::StorageValueRef,
+	traits::{AtLeast32BitUnsigned, Saturating},
+	RuntimeDebug,
+};
+use sp_std::prelude::*;
+
+pub const DEFAULT_TIMEOUT_PERIOD: u64 = 3000; // in milli-seconds
+pub const DEFAULT_MAX_RESPONSE_SIZE: usize = 64 * 1024; // in bytes
+pub const DEFAULT_BACKOFF_BASE: u32 = 1; // in block number
+pub const DEFAULT_BACKOFF_MAX: u32 = 64; // in block number
+
+/// Why a fetch did not produce a response body.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum HttpError {
+	/// The request could not be sent or the connection failed.
+	Unreachable,
+	/// No response arrived before the deadline.
+	Timeout,
+	/// The server answered with a status code other than `200`.
+	Status(u16),
+	/// The response body is larger than the configured limit.
+	TooLarge,
+	/// A previous failure put this client on hold, so no request was sent.
+	BackingOff,
+}
+
+/// Consecutive failures of a client and the block at which it may try again.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct BackoffState<BlockNumber> {
+	pub failures: u32,
+	pub retry_at: BlockNumber,
+}
+
+/// An HTTP GET client. `id` identifies the client in offchain storage, so it should be
+///   prefixed with the pallet name, e.g. `b"dot-price::feed::DOT"`.
+pub struct HttpClient<'a> {
+	id: &'a [u8],
+	urls: Vec<&'a str>,
+	headers: Vec<(&'a str, &'a str)>,
+	timeout: u64,
+	max_response_size: usize,
+	backoff_base: u32,
+	backoff_max: u32,
+}
+
+impl<'a> HttpClient<'a> {
+	pub fn new(id: &'a [u8]) -> Self {
+		HttpClient {
+			id,
+			urls: Vec::new(),
+			headers: Vec::new(),
+			timeout: DEFAULT_TIMEOUT_PERIOD,
+			max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+			backoff_base: DEFAULT_BACKOFF_BASE,
+			backoff_max: DEFAULT_BACKOFF_MAX,
+		}
+	}
+
+	/// Add a URL. URLs are tried in the order they were added.
+	pub fn add_url(mut self, url: &'a str) -> Self {
+		self.urls.push(url);
+		self
+	}
+
+	pub fn add_header(mut self, name: &'a str, value: &'a str) -> Self {
+		self.headers.push((name, value));
+		self
+	}
+
+	/// Deadline for each URL, in milli-seconds.
+	pub fn timeout(mut self, millis: u64) -> Self {
+		self.timeout = millis;
+		self
+	}
+
+	pub fn max_response_size(mut self, bytes: usize) -> Self {
+		self.max_response_size = bytes;
+		self
+	}
+
+	/// After `n` consecutive failed fetches the client waits `base * 2^(n-1)` blocks, capped
+	///   at `max` blocks, before it sends requests again.
+	pub fn backoff(mut self, base: u32, max: u32) -> Self {
+		self.backoff_base = base;
+		self.backoff_max = max;
+		self
+	}
+
+	fn backoff_key(&self) -> Vec<u8> {
+		[self.id, b"::backoff"].concat()
+	}
+
+	/// The backoff state stored for this client, if any.
+	pub fn backoff_state<B: Codec>(&self) -> Option<BackoffState<B>> {
+		StorageValueRef::persistent(&self.backoff_key()).get::<BackoffState<B>>().flatten()
+	}
+
+	/// Fetch the first URL that answers with `200` and return its body.
+	///
+	/// When every URL fails, the error of the last one is returned and the backoff is extended.
+	///   While backing off, no request is sent and `HttpError::BackingOff` is returned.
+	pub fn fetch<B>(&self, block_number: B) -> Result<Vec<u8>, HttpError>
+	where
+		B: AtLeast32BitUnsigned + Codec + Copy,
+	{
+		let mut storage = StorageValueRef::persistent(&self.backoff_key());
+		let state = storage.get::<BackoffState<B>>().flatten().unwrap_or_default();
+		if state.failures > 0 && block_number < state.retry_at {
+			return Err(HttpError::BackingOff);
+		}
+
+		let mut last_error = HttpError::Unreachable;
+		for url in self.urls.iter() {
+			match self.fetch_one(url) {
+				Ok(body) => {
+					if state.failures > 0 {
+						storage.clear();
+					}
+					return Ok(body);
+				}
+				Err(e) => last_error = e,
+			}
+		}
+
+		let failures = state.failures.saturating_add(1);
+		storage.set(&BackoffState {
+			failures,
+			retry_at: block_number.saturating_add(B::from(self.backoff_delay(failures))),
+		});
+		Err(last_error)
+	}
+
+	/// Number of blocks to wait after `failures` consecutive failed fetches.
+	pub fn backoff_delay(&self, failures: u32) -> u32 {
+		if failures == 0 {
+			return 0;
+		}
+		self.backoff_base
+			.saturating_mul(1u32.checked_shl(failures - 1).unwrap_or(u32::max_value()))
+			.min(self.backoff_max)
+	}
+
+	fn fetch_one(&self, url: &str) -> Result<Vec<u8>, HttpError> {
+		let deadline =
+			sp_io::offchain::timestamp().add(rt_offchain::Duration::from_millis(self.timeout));
+
+		let mut request = rt_offchain::http::Request::get(url);
+		for (name, value) in self.headers.iter() {
+			request = request.add_header(*name, *value);
+		}
+		let pending = request
+			.deadline(deadline)
+			.send()
+			.map_err(|_| HttpError::Unreachable)?;
+
+		// `try_wait` returns `Err` when the deadline is reached before the request completes,
+		//   and `Ok(Err(_))` when the request itself failed.
+		let mut response = pending
+			.try_wait(deadline)
+			.map_err(|_| HttpError::Timeout)?
+			.map_err(|e| match e {
+				rt_offchain::http::Error::DeadlineReached => HttpError::Timeout,
+				_ => HttpError::Unreachable,
+			})?;
+
+		if response.code != 200 {
+			return Err(HttpError::Status(response.code));
+		}
+
+		// Refuse early when the server announces a body that is too large.
+		let announced = response
+			.headers()
+			.find("content-length")
+			.and_then(|len| len.parse::<usize>().ok());
+		if announced.map_or(false, |len| len > self.max_response_size) {
+			return Err(HttpError::TooLarge);
+		}
+
+		// Read at most one byte more than allowed, so an oversized body is detected without
+		//   buffering all of it.
+		let mut body = response.body();
+		let bytes = body
+			.by_ref()
+			.take(self.max_response_size + 1)
+			.collect::<Vec<u8>>();
+		if bytes.len() > self.max_response_size {
+			return Err(HttpError::TooLarge);
+		}
+		if body.error().is_some() {
+			return Err(HttpError::Unreachable);
+		}
+		Ok(bytes)
+	}
+}
@@ -0,0 +1,120 @@
+use crate::*;
+use parking_lot::RwLock;
+use sp_core::offchain::{
+	testing::{self, OffchainState, PendingRequest},
+	OffchainExt,
+};
+use sp_io::TestExternalities;
+use std::sync::Arc;
+
+const PRIMARY: &str = "https://primary.example/price";
+const FALLBACK: &str = "https://fallback.example/price";
+const CLIENT_ID: &[u8] = b"ocw-http::test";
+
+fn new_test_ext() -> (TestExternalities, Arc<RwLock<OffchainState>>) {
+	let (offchain, state) = testing::TestOffchainExt::new();
+	let mut t = TestExternalities::default();
+	t.register_extension(OffchainExt::new(offchain));
+	(t, state)
+}
+
+fn expect_get(state: &Arc<RwLock<OffchainState>>, uri: &str, body: &[u8]) {
+	state.write().expect_request(PendingRequest {
+		method: "GET".into(),
+		uri: uri.into(),
+		headers: vec![("User-Agent".into(), "ocw-http".into())],
+		response: Some(body.to_vec()),
+		sent: true,
+		..Default::default()
+	});
+}
+
+fn client() -> HttpClient<'static> {
+	HttpClient::new(CLIENT_ID)
+		.add_url(PRIMARY)
+		.add_url(FALLBACK)
+		.add_header("User-Agent", "ocw-http")
+		.backoff(2, 8)
+}
+
+fn set_backoff(failures: u32, retry_at: u32) {
+	StorageValueRef::persistent(&[CLIENT_ID, b"::backoff"].concat())
+		.set(&BackoffState { failures, retry_at });
+}
+
+#[test]
+fn returns_body_of_first_url() {
+	let (mut t, state) = new_test_ext();
+	expect_get(&state, PRIMARY, b"42");
+
+	t.execute_with(|| {
+		assert_eq!(client().fetch(1u32), Ok(b"42".to_vec()));
+		assert_eq!(client().backoff_state::<u32>(), None);
+	});
+}
+
+#[test]
+fn no_request_is_sent_while_backing_off() {
+	let (mut t, state) = new_test_ext();
+
+	t.execute_with(|| {
+		set_backoff(2, 10);
+		// the mock panics on any request that was not expected
+		assert_eq!(client().fetch(9u32), Err(HttpError::BackingOff));
+
+		expect_get(&state, PRIMARY, b"42");
+		assert_eq!(client().fetch(10u32), Ok(b"42".to_vec()));
+		// a success resets the backoff
+		assert_eq!(client().backoff_state::<u32>(), None);
+	});
+}
+
+#[test]
+fn backoff_grows_exponentially_and_is_capped() {
+	let client = client();
+	assert_eq!(client.backoff_delay(0), 0);
+	assert_eq!(client.backoff_delay(1), 2);
+	assert_eq!(client.backoff_delay(2), 4);
+	assert_eq!(client.backoff_delay(3), 8);
+	assert_eq!(client.backoff_delay(4), 8);
+	assert_eq!(client.backoff_delay(u32::max_value()), 8);
+}
+
+#[test]
+fn rejects_oversized_response() {
+	let (mut t, state) = new_test_ext();
+	expect_get(&state, PRIMARY, &[0u8; 17]);
+	expect_get(&state, PRIMARY, &[0u8; 16]);
+
+	t.execute_with(|| {
+		let client = HttpClient::new(CLIENT_ID)
+			.add_url(PRIMARY)
+			.add_header("User-Agent", "ocw-http")
+			.max_response_size(16);
+		assert_eq!(client.fetch(1u32), Err(HttpError::TooLarge));
+		assert_eq!(
+			client.backoff_state::<u32>(),
+			Some(BackoffState { failures: 1, retry_at: 2 })
+		);
+
+		assert_eq!(client.fetch(2u32), Ok(vec![0u8; 16]));
+	});
+}
+
+#[test]
+fn rejects_announced_oversized_response() {
+	let (mut t, state) = new_test_ext();
+	state.write().expect_request(PendingRequest {
+		method: "GET".into(),
+		uri: PRIMARY.into(),
+		response: Some(b"{}".to_vec()),
+		response_headers: vec![("content-length".into(), "1048576".into())],
+		sent: true,
+		..Default::default()
+	});
+
+	t.execute_with(|| {
+		let client = HttpClient::new(CLIENT_ID).add_url(PRIMARY);
+		assert_eq!(client.fetch(1u32), Err(HttpError::TooLarge));
+	});
+}
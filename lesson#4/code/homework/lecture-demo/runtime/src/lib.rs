@@ -33,6 +33,7 @@ pub use sp_runtime::{Permill, Perbill};
 pub use frame_support::{
 	construct_runtime, debug, parameter_types, StorageValue,
 	traits::{KeyOwnerProofSystem, Randomness},
+	u32_trait::{_2, _3},
 	weights::{
 		Weight, IdentityFee,
 		constants::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight, WEIGHT_PER_SECOND},
@@ -90,6 +91,7 @@ pub mod opaque {
 		pub struct SessionKeys {
 			pub aura: Aura,
 			pub grandpa: Grandpa,
+			pub demo: OcwDemo,
 		}
 	}
 }
@@ -144,7 +146,7 @@ impl frame_system::Trait for Runtime {
 	/// The aggregated dispatch type that is available for extrinsics.
 	type Call = Call;
 	/// The lookup mechanism to get account ID from whatever is passed in dispatchers.
-	type Lookup = multiaddress::AccountIdLookup<AccountId, ()>;
+	type Lookup = multiaddress::AccountIdLookup<AccountId, AccountIndex, Indices>;
 	/// The index type for storing how many extrinsics an account has signed.
 	type Index = Index;
 	/// The index type for blocks.
@@ -218,6 +220,28 @@ impl pallet_grandpa::Trait for Runtime {
 	type WeightInfo = ();
 }
 
+parameter_types! {
+	pub const SessionPeriod: BlockNumber = 10 * MINUTES;
+	pub const SessionOffset: BlockNumber = 0;
+	pub const DisabledValidatorsThreshold: Perbill = Perbill::from_percent(17);
+}
+
+/// The validator set is fixed by the genesis config, sessions only rotate the keys set by
+///   `Session::set_keys`.
+impl pallet_session::Trait for Runtime {
+	type Event = Event;
+	type ValidatorId = AccountId;
+	type ValidatorIdOf = sp_runtime::traits::ConvertInto;
+	type ShouldEndSession = pallet_session::PeriodicSessions<SessionPeriod, SessionOffset>;
+	type NextSessionRotation = pallet_session::PeriodicSessions<SessionPeriod, SessionOffset>;
+	type SessionManager = ();
+	// OcwDemo keeps the `demo` keys of the validators, DotPrice reads them from it.
+	type SessionHandler = (Aura, Grandpa, OcwDemo);
+	type Keys = opaque::SessionKeys;
+	type DisabledValidatorsThreshold = DisabledValidatorsThreshold;
+	type WeightInfo = ();
+}
+
 parameter_types! {
 	pub const MinimumPeriod: u64 = SLOT_DURATION / 2;
 }
@@ -247,6 +271,19 @@ impl pallet_balances::Trait for Runtime {
 	type WeightInfo = ();
 }
 
+parameter_types! {
+	/// Reserved while an account holds an index.
+	pub const IndexDeposit: Balance = 100 * ExistentialDeposit::get();
+}
+
+impl pallet_indices::Trait for Runtime {
+	type AccountIndex = AccountIndex;
+	type Currency = Balances;
+	type Deposit = IndexDeposit;
+	type Event = Event;
+	type WeightInfo = ();
+}
+
 parameter_types! {
 	pub const TransactionByteFee: Balance = 1;
 }
@@ -264,16 +301,75 @@ impl pallet_sudo::Trait for Runtime {
 	type Call = Call;
 }
 
+parameter_types! {
+	pub const CouncilMotionDuration: BlockNumber = 3 * DAYS;
+	pub const CouncilMaxProposals: u32 = 100;
+	pub const CouncilMaxMembers: u32 = 100;
+}
+
+type CouncilCollective = pallet_collective::Instance1;
+impl pallet_collective::Trait<CouncilCollective> for Runtime {
+	type Origin = Origin;
+	type Proposal = Call;
+	type Event = Event;
+	type MotionDuration = CouncilMotionDuration;
+	type MaxProposals = CouncilMaxProposals;
+	type MaxMembers = CouncilMaxMembers;
+	type DefaultVote = pallet_collective::PrimeDefaultVote;
+	type WeightInfo = ();
+}
+
+/// A motion passed by 2/3 of the council.
+type EnsureTwoThirdsCouncil =
+	pallet_collective::EnsureProportionAtLeast<_2, _3, AccountId, CouncilCollective>;
+
+impl pallet_membership::Trait<pallet_membership::Instance1> for Runtime {
+	type Event = Event;
+	type AddOrigin = EnsureTwoThirdsCouncil;
+	type RemoveOrigin = EnsureTwoThirdsCouncil;
+	type SwapOrigin = EnsureTwoThirdsCouncil;
+	type ResetOrigin = EnsureTwoThirdsCouncil;
+	type PrimeOrigin = EnsureTwoThirdsCouncil;
+	type MembershipInitialized = Council;
+	type MembershipChanged = Council;
+}
+
 /// Configure the template pallet in pallets/template.
 impl pallet_template::Trait for Runtime {
 	type Event = Event;
 }
 
+parameter_types! {
+	pub const OcwUnsignedInterval: BlockNumber = 2;
+	pub const OcwAllowUnsignedWithoutPayload: bool = false;
+}
+
 /// For pallet-ocw-demo
 impl pallet_ocw_demo::Trait for Runtime {
 	type AuthorityId = pallet_ocw_demo::crypto::TestAuthId;
 	type Call = Call;
 	type Event = Event;
+	type UnsignedInterval = OcwUnsignedInterval;
+	type AllowUnsignedWithoutPayload = OcwAllowUnsignedWithoutPayload;
+	/// Job periods and the statistics window are changed by council motions.
+	type ManageOrigin = EnsureTwoThirdsCouncil;
+}
+
+parameter_types! {
+	pub const OracleQuorum: u32 = 2;
+	pub const OracleMaxDeviation: Permill = Permill::from_percent(5);
+	pub const OracleSubmissionWindow: BlockNumber = 3;
+}
+
+/// The `demo` session keys, which both offchain worker pallets sign with.
+pub struct OcwSessionAuthorities;
+
+impl pallet_dot_price::SessionAuthorities<<Runtime as frame_system::offchain::SigningTypes>::Public>
+	for OcwSessionAuthorities
+{
+	fn session_authorities() -> Vec<<Runtime as frame_system::offchain::SigningTypes>::Public> {
+		OcwDemo::session_authorities()
+	}
 }
 
 /// For pallet-dot-price
@@ -281,6 +377,12 @@ impl pallet_dot_price::Trait for Runtime {
 	type AuthorityId = pallet_dot_price::crypto::TestAuthId;
 	type Call = Call;
 	type Event = Event;
+	type Quorum = OracleQuorum;
+	type MaxDeviation = OracleMaxDeviation;
+	type SubmissionWindow = OracleSubmissionWindow;
+	type SessionAuthorities = OcwSessionAuthorities;
+	/// Fetch endpoints and oracles are changed by council motions.
+	type ManageOrigin = EnsureTwoThirdsCouncil;
 }
 
 impl<LocalCall> frame_system::offchain::CreateSignedTransaction<LocalCall> for Runtime
@@ -345,19 +447,23 @@ construct_runtime!(
 		Timestamp: pallet_timestamp::{Module, Call, Storage, Inherent},
 		Aura: pallet_aura::{Module, Config<T>, Inherent},
 		Grandpa: pallet_grandpa::{Module, Call, Storage, Config, Event},
+		Session: pallet_session::{Module, Call, Storage, Event, Config<T>},
 		Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
+		Indices: pallet_indices::{Module, Call, Storage, Config<T>, Event<T>},
 		TransactionPayment: pallet_transaction_payment::{Module, Storage},
 		Sudo: pallet_sudo::{Module, Call, Config<T>, Storage, Event<T>},
+		Council: pallet_collective::<Instance1>::{Module, Call, Storage, Origin<T>, Event<T>, Config<T>},
+		CouncilMembership: pallet_membership::<Instance1>::{Module, Call, Storage, Event<T>, Config<T>},
 		// Include the custom logic from the template pallet in the runtime.
 		TemplateModule: pallet_template::{Module, Call, Storage, Event<T>},
 		OcwDemo: pallet_ocw_demo::{Module, Call, Storage, Event<T>, ValidateUnsigned},
-		DotPrice: pallet_dot_price::{Module, Call, Storage, Event<T>, ValidateUnsigned},
+		DotPrice: pallet_dot_price::{Module, Call, Storage, Event<T>, Config<T>, ValidateUnsigned},
 	}
 );
 
 /// The address format for describing accounts.
 mod multiaddress;
-pub type Address = multiaddress::MultiAddress<AccountId, ()>;
+pub type Address = multiaddress::MultiAddress<AccountId, AccountIndex>;
 /// Block header type as expected by this runtime.
 pub type Header = generic::Header<BlockNumber, BlakeTwo256>;
 /// Block type as expected by this runtime.
@@ -517,6 +623,22 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_ocw_demo_runtime_api::OcwDemoApi<Block> for Runtime {
+		fn number_stats() -> pallet_ocw_demo::NumberStats {
+			OcwDemo::number_stats()
+		}
+	}
+
+	impl pallet_dot_price_runtime_api::DotPriceApi<Block> for Runtime {
+		fn latest_price(symbol: pallet_dot_price::Symbol) -> Option<pallet_dot_price::FinalizedPrice> {
+			DotPrice::latest_price(&symbol)
+		}
+
+		fn price_history(symbol: pallet_dot_price::Symbol, n: u32) -> Vec<pallet_dot_price::Price> {
+			DotPrice::price_history(&symbol, n)
+		}
+	}
+
 	#[cfg(feature = "runtime-benchmarks")]
 	impl frame_benchmarking::Benchmark<Block> for Runtime {
 		fn dispatch_benchmark(
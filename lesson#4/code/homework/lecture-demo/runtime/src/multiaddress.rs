@@ -68,23 +68,112 @@ impl<AccountId: Default, AccountIndex> Default for MultiAddress<AccountId, Accou
 	}
 }
 
+/// Resolves the `MultiAddress::Index` variant for `AccountIdLookup`.
+pub trait IndexLookup<AccountId, AccountIndex> {
+	/// The account that holds `index`, if it is claimed.
+	fn lookup_index(index: AccountIndex) -> Option<AccountId>;
+}
+
+/// No account indices; `MultiAddress::Index` never resolves.
+impl<AccountId, AccountIndex> IndexLookup<AccountId, AccountIndex> for () {
+	fn lookup_index(_: AccountIndex) -> Option<AccountId> {
+		None
+	}
+}
+
+impl<T: pallet_indices::Trait> IndexLookup<T::AccountId, T::AccountIndex> for pallet_indices::Module<T> {
+	fn lookup_index(index: T::AccountIndex) -> Option<T::AccountId> {
+		pallet_indices::Module::<T>::lookup_index(index)
+	}
+}
+
+/// Decodes `bytes` as an `AccountId`, which must use up all of them.
+fn decode_account<AccountId: Decode>(mut bytes: &[u8]) -> Result<AccountId, LookupError> {
+	let account = AccountId::decode(&mut bytes).map_err(|_| LookupError)?;
+	if bytes.is_empty() { Ok(account) } else { Err(LookupError) }
+}
+
 /// A lookup implementation returning the `AccountId` from a `MultiAddress`.
-pub struct AccountIdLookup<AccountId, AccountIndex>(PhantomData<(AccountId, AccountIndex)>);
-impl<AccountId, AccountIndex> StaticLookup for AccountIdLookup<AccountId, AccountIndex>
+///
+/// `Index` is resolved through `Indices`, while `Raw` and `Address32` must hold exactly the
+/// encoded `AccountId`. `Address20` is not supported.
+pub struct AccountIdLookup<AccountId, AccountIndex, Indices = ()>(
+	PhantomData<(AccountId, AccountIndex, Indices)>
+);
+impl<AccountId, AccountIndex, Indices> StaticLookup for AccountIdLookup<AccountId, AccountIndex, Indices>
 where
 	AccountId: Codec + Clone + PartialEq + Debug,
 	AccountIndex: Codec + Clone + PartialEq + Debug,
 	MultiAddress<AccountId, AccountIndex>: Codec,
+	Indices: IndexLookup<AccountId, AccountIndex>,
 {
 	type Source = MultiAddress<AccountId, AccountIndex>;
 	type Target = AccountId;
 	fn lookup(x: Self::Source) -> Result<Self::Target, LookupError> {
 		match x {
 			MultiAddress::Id(i) => Ok(i),
-			_ => Err(LookupError),
+			MultiAddress::Index(i) => Indices::lookup_index(i).ok_or(LookupError),
+			MultiAddress::Raw(bytes) => decode_account(&bytes),
+			MultiAddress::Address32(bytes) => decode_account(&bytes),
+			MultiAddress::Address20(_) => Err(LookupError),
 		}
 	}
 	fn unlookup(x: Self::Target) -> Self::Source {
 		MultiAddress::Id(x)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::crypto::AccountId32;
+
+	/// Index `n` belongs to the account whose bytes are all `n`.
+	struct TestIndices;
+	impl IndexLookup<AccountId32, u32> for TestIndices {
+		fn lookup_index(index: u32) -> Option<AccountId32> {
+			if index < 10 { Some(AccountId32::new([index as u8; 32])) } else { None }
+		}
+	}
+
+	type Lookup = AccountIdLookup<AccountId32, u32, TestIndices>;
+	type Address = MultiAddress<AccountId32, u32>;
+
+	fn alice() -> AccountId32 {
+		AccountId32::new([1; 32])
+	}
+
+	#[test]
+	fn id_is_returned_as_is() {
+		assert_eq!(Lookup::lookup(Address::Id(alice())), Ok(alice()));
+		assert_eq!(Lookup::unlookup(alice()), Address::Id(alice()));
+	}
+
+	#[test]
+	fn index_is_resolved_through_indices() {
+		assert_eq!(Lookup::lookup(Address::Index(1)), Ok(alice()));
+		assert_eq!(Lookup::lookup(Address::Index(10)), Err(LookupError));
+		assert_eq!(
+			AccountIdLookup::<AccountId32, u32>::lookup(Address::Index(1)),
+			Err(LookupError),
+		);
+	}
+
+	#[test]
+	fn raw_must_be_an_encoded_account_id() {
+		assert_eq!(Lookup::lookup(Address::Raw(vec![1; 32])), Ok(alice()));
+		assert_eq!(Lookup::lookup(Address::Raw(vec![1; 31])), Err(LookupError));
+		assert_eq!(Lookup::lookup(Address::Raw(vec![1; 33])), Err(LookupError));
+		assert_eq!(Lookup::lookup(Address::Raw(vec![])), Err(LookupError));
+	}
+
+	#[test]
+	fn address32_decodes_into_account_id32() {
+		assert_eq!(Lookup::lookup(Address::Address32([1; 32])), Ok(alice()));
+	}
+
+	#[test]
+	fn address20_is_not_supported() {
+		assert_eq!(Lookup::lookup(Address::Address20([1; 20])), Err(LookupError));
+	}
+}